@@ -0,0 +1,504 @@
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::audit::{AuditLog, KillRecord, Source};
+use crate::cli::UsageError;
+use crate::config::{self, Config};
+use crate::lsof::{self, KillOutcome, LsofEntry, PortOutcome, Scanner};
+use crate::protect::{Guard, Protection};
+use crate::timeline::{self, EventKind};
+
+/// Same cadence as the TUI's refresh.
+const SCAN_INTERVAL: Duration = Duration::from_secs(1);
+
+pub(crate) fn default_socket() -> Option<PathBuf> {
+    config::runtime_dir().map(|d| d.join("agent.sock"))
+}
+
+/// One JSON object per line. `list` and `kill` get one response line
+/// each; `subscribe` turns the connection into a stream of `Event`s.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub(crate) enum Request {
+    List,
+    Subscribe,
+    Kill {
+        pid: String,
+        starttime_ticks: u64,
+        /// The listener's port, typed by a person: required to kill a
+        /// rule-protected process, as at the TUI and CLI prompts.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        confirm_port: Option<u16>,
+    },
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct Response {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) listeners: Option<Vec<LsofEntry>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) outcome: Option<KillOutcome>,
+    /// Kill: what holds the port now that the process is gone.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) port: Option<PortOutcome>,
+}
+
+fn refuse(message: String) -> Response {
+    Response {
+        error: Some(message),
+        ..Default::default()
+    }
+}
+
+/// A change between two scans, pushed to subscribers.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Event {
+    /// `opened`, `closed` or `owner-changed`.
+    pub(crate) event: String,
+    pub(crate) listener: LsofEntry,
+    /// For `owner-changed`: who held the address before.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) previous: Option<LsofEntry>,
+}
+
+fn events(old: &[LsofEntry], new: &[LsofEntry]) -> Vec<Event> {
+    timeline::diff(old, new)
+        .into_iter()
+        .map(|(kind, listener)| {
+            let (event, previous) = match kind {
+                EventKind::Opened => ("opened", None),
+                EventKind::Closed => ("closed", None),
+                EventKind::OwnerChanged { previous } => ("owner-changed", Some(*previous)),
+            };
+            Event {
+                event: event.to_string(),
+                listener,
+                previous,
+            }
+        })
+        .collect()
+}
+
+fn to_line(value: &impl Serialize) -> io::Result<String> {
+    let mut line = serde_json::to_string(value).map_err(io::Error::other)?;
+    line.push('\n');
+    Ok(line)
+}
+
+struct Shared {
+    scanner: Scanner,
+    latest: Vec<LsofEntry>,
+    subscribers: Vec<Sender<String>>,
+    guard: Guard,
+    audit: AuditLog,
+    read_only: bool,
+}
+
+impl Shared {
+    fn rescan(&mut self) {
+        let scan = self.scanner.scan();
+        let lines: Vec<String> = events(&self.latest, &scan)
+            .iter()
+            .filter_map(|e| to_line(e).ok())
+            .collect();
+        // A subscriber that hung up is dropped on the first failed send.
+        self.subscribers
+            .retain(|tx| lines.iter().all(|l| tx.send(l.clone()).is_ok()));
+        self.latest = scan;
+    }
+
+    /// The listener a kill request names, if the agent will kill it.
+    fn target(
+        &mut self,
+        pid: &str,
+        ticks: u64,
+        confirm_port: Option<u16>,
+    ) -> Result<LsofEntry, String> {
+        if self.read_only {
+            return Err("this agent is read-only".to_string());
+        }
+        // Against a fresh scan: the client's view may be a second old.
+        self.rescan();
+        let Some(target) = self
+            .latest
+            .iter()
+            .find(|p| p.pid == pid && p.starttime_ticks == Some(ticks))
+            .cloned()
+        else {
+            return Err(format!(
+                "pid {} (starttime {}) holds no listener — exited, or the PID was recycled",
+                pid, ticks
+            ));
+        };
+        match self.guard.check(&target) {
+            Some(Protection::Pinned(why)) => Err(format!("won't kill: {}", why)),
+            Some(rule) if confirm_port != Some(target.port) => Err(format!(
+                "{}; send confirm_port {} to kill it",
+                rule.reason(),
+                target.port
+            )),
+            _ => Ok(target),
+        }
+    }
+}
+
+fn kill(
+    shared: &Mutex<Shared>,
+    pid: &str,
+    ticks: u64,
+    confirm_port: Option<u16>,
+    client: u32,
+) -> Response {
+    let target = match lock(shared).target(pid, ticks, confirm_port) {
+        Ok(target) => target,
+        Err(refusal) => return refuse(refusal),
+    };
+    // Not under the lock: the kill waits out SIGTERM's grace period and
+    // the port check watches for a respawn, and list clients and the
+    // scan thread shouldn't wait with them.
+    let before = lsof::port_inodes(target.port);
+    let outcome = lsof::kill_process_verified(&target.pid, target.starttime_ticks);
+    let port = match outcome {
+        Ok(KillOutcome::StillAlive) | Err(_) => None,
+        Ok(_) => Some(Scanner::default().await_port_release(&target, &before)),
+    };
+    let mut record = KillRecord::new(Source::Agent, &target, &outcome);
+    record.user = lsof::user_name(client);
+    record.sudo_user = None;
+    let mut shared = lock(shared);
+    // Subscribers hear of the closed listener now, not at the next tick.
+    shared.rescan();
+    if let Err(e) = shared.audit.append(&record) {
+        eprintln!("reaper agent: kill not audited: {}", e);
+    }
+    match outcome {
+        Ok(outcome) => Response {
+            outcome: Some(outcome),
+            port,
+            ..Default::default()
+        },
+        Err(e) => refuse(e.to_string()),
+    }
+}
+
+/// uid of the process on the other end of `stream`.
+pub(crate) fn peer_uid(stream: &UnixStream) -> Option<u32> {
+    use std::os::fd::AsRawFd;
+    let mut cred: libc::ucred = unsafe { std::mem::zeroed() };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    let rc = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            (&mut cred as *mut libc::ucred).cast(),
+            &mut len,
+        )
+    };
+    (rc == 0).then_some(cred.uid)
+}
+
+fn serve(stream: UnixStream, shared: &Mutex<Shared>) -> io::Result<()> {
+    let client = peer_uid(&stream).unwrap_or(u32::MAX);
+    let mut output = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str(&line) {
+            Ok(Request::List) => Response {
+                listeners: Some(lock(shared).latest.clone()),
+                ..Default::default()
+            },
+            Ok(Request::Kill {
+                pid,
+                starttime_ticks,
+                confirm_port,
+            }) => kill(shared, &pid, starttime_ticks, confirm_port, client),
+            Ok(Request::Subscribe) => {
+                let (tx, rx) = mpsc::channel();
+                lock(shared).subscribers.push(tx);
+                for event in rx {
+                    output.write_all(event.as_bytes())?;
+                }
+                return Ok(());
+            }
+            Err(e) => refuse(format!("bad request: {}", e)),
+        };
+        output.write_all(to_line(&response)?.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// A panicked connection thread mustn't take the agent down with it.
+fn lock(shared: &Mutex<Shared>) -> std::sync::MutexGuard<'_, Shared> {
+    shared.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// `reaper agent [--listen PATH] [--group NAME]`: scan once a second and
+/// serve the results on a Unix socket, by default
+/// `$XDG_RUNTIME_DIR/reaper/agent.sock`. The socket is mode 0600 — only
+/// reaper's own user may connect — or 0660 with `--group`.
+pub(crate) fn run(args: &[String], config: &Config) -> Result<(), UsageError> {
+    let mut listen = None;
+    let mut group = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .cloned()
+                .ok_or_else(|| UsageError(format!("{} needs a value", arg)))
+        };
+        match arg.as_str() {
+            "--listen" => listen = Some(PathBuf::from(value()?)),
+            "--group" => group = Some(value()?),
+            other => return Err(UsageError(format!("unknown agent option: {}", other))),
+        }
+    }
+    let Some(path) = listen.or_else(default_socket) else {
+        return Err(UsageError(
+            "no runtime directory for the socket; pass --listen PATH".to_string(),
+        ));
+    };
+    if let Err(e) = listen_on(&path, group.as_deref(), config) {
+        eprintln!("reaper agent: {}: {}", path.display(), e);
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn listen_on(path: &Path, group: Option<&str>, config: &Config) -> io::Result<()> {
+    let gid = match group {
+        Some(name) => Some(
+            lsof::group_id(name)
+                .ok_or_else(|| io::Error::other(format!("no such group: {}", name)))?,
+        ),
+        None => None,
+    };
+    if let Some(dir) = path.parent() {
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)?;
+    }
+    // Only clear the way if nobody answers there — never steal a live
+    // agent's socket.
+    if UnixStream::connect(path).is_ok() {
+        return Err(io::Error::other("another agent is already listening"));
+    }
+    let _ = fs::remove_file(path);
+    let listener = UnixListener::bind(path)?;
+    if let Some(gid) = gid {
+        std::os::unix::fs::chown(path, None, Some(gid))?;
+    }
+    let mode = if gid.is_some() { 0o660 } else { 0o600 };
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+
+    let shared = Arc::new(Mutex::new(Shared {
+        scanner: Scanner::default(),
+        latest: Vec::new(),
+        subscribers: Vec::new(),
+        guard: Guard::new(config.protected.clone()),
+        audit: AuditLog::from_config(config),
+        read_only: config.read_only,
+    }));
+    lock(&shared).rescan();
+    let scanning = Arc::clone(&shared);
+    std::thread::spawn(move || {
+        loop {
+            std::thread::sleep(SCAN_INTERVAL);
+            lock(&scanning).rescan();
+        }
+    });
+    eprintln!("reaper agent listening on {}", path.display());
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let shared = Arc::clone(&shared);
+        std::thread::spawn(move || {
+            let _ = serve(stream, &shared);
+        });
+    }
+    Ok(())
+}
+
+/// The TUI's connection to an agent, for `--connect`. Reconnects on the
+/// next call after the agent goes away, so restarting the agent doesn't
+/// mean restarting the TUI.
+#[derive(Debug)]
+pub struct Client {
+    path: PathBuf,
+    stream: Option<BufReader<UnixStream>>,
+    /// Why the last request failed, while the agent is unreachable.
+    pub(crate) error: Option<String>,
+    /// What the agent saw of the port after the last kill, until the
+    /// scanner asks for it.
+    pub(crate) port: Option<PortOutcome>,
+}
+
+impl Client {
+    /// Connect now, so a wrong path fails at startup rather than as an
+    /// empty table.
+    pub(crate) fn connect(path: PathBuf) -> io::Result<Client> {
+        let stream = UnixStream::connect(&path)?;
+        Ok(Client {
+            path,
+            stream: Some(BufReader::new(stream)),
+            error: None,
+            port: None,
+        })
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn request(&mut self, request: &Request) -> io::Result<Response> {
+        let result = self.try_request(request);
+        match &result {
+            Ok(_) => self.error = None,
+            Err(e) => {
+                self.stream = None;
+                self.error = Some(e.to_string());
+            }
+        }
+        let response = result?;
+        match response.error {
+            Some(e) => Err(io::Error::other(e)),
+            None => Ok(response),
+        }
+    }
+
+    fn try_request(&mut self, request: &Request) -> io::Result<Response> {
+        let stream = match &mut self.stream {
+            Some(stream) => stream,
+            None => self
+                .stream
+                .insert(BufReader::new(UnixStream::connect(&self.path)?)),
+        };
+        stream.get_mut().write_all(to_line(request)?.as_bytes())?;
+        let mut reply = String::new();
+        if stream.read_line(&mut reply)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "agent closed the connection",
+            ));
+        }
+        serde_json::from_str(&reply).map_err(io::Error::other)
+    }
+
+    pub(crate) fn list(&mut self) -> io::Result<Vec<LsofEntry>> {
+        Ok(self.request(&Request::List)?.listeners.unwrap_or_default())
+    }
+
+    /// The TUI has already had the kill confirmed — with the port typed
+    /// out, if its own rules protect the process — so it vouches for
+    /// the port.
+    pub(crate) fn kill(&mut self, p: &LsofEntry) -> io::Result<KillOutcome> {
+        let Some(ticks) = p.starttime_ticks else {
+            return Err(io::Error::other("unknown start time; refusing to kill"));
+        };
+        let response = self.request(&Request::Kill {
+            pid: p.pid.clone(),
+            starttime_ticks: ticks,
+            confirm_port: Some(p.port),
+        })?;
+        self.port = response.port;
+        response
+            .outcome
+            .ok_or_else(|| io::Error::other("agent sent no outcome"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(pid: &str, port: u16, starttime_ticks: Option<u64>) -> LsofEntry {
+        LsofEntry {
+            command: "node".into(),
+            starttime_ticks,
+            ..LsofEntry::test(pid, port, 1)
+        }
+    }
+
+    #[test]
+    fn requests_are_tagged_json() {
+        assert_eq!(
+            serde_json::from_str::<Request>(r#"{"op":"kill","pid":"7","starttime_ticks":9}"#)
+                .unwrap(),
+            Request::Kill {
+                pid: "7".into(),
+                starttime_ticks: 9,
+                confirm_port: None
+            }
+        );
+        assert_eq!(
+            serde_json::to_string(&Request::Subscribe).unwrap(),
+            r#"{"op":"subscribe"}"#
+        );
+    }
+
+    #[test]
+    fn entries_round_trip() {
+        let mut e = entry("5", 8080, Some(3));
+        e.protocol = "TCP4/6";
+        let back: LsofEntry = serde_json::from_str(&serde_json::to_string(&e).unwrap()).unwrap();
+        assert_eq!(back.protocol, "TCP4/6");
+        assert_eq!(back.id(), e.id());
+        assert!(
+            serde_json::from_str::<LsofEntry>(
+                &serde_json::to_string(&e).unwrap().replace("TCP4/6", "UDP")
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn open_and_close_become_events() {
+        let got: Vec<_> = events(&[entry("5", 80, Some(3))], &[entry("5", 443, Some(3))])
+            .into_iter()
+            .map(|e| (e.event, e.listener.port))
+            .collect();
+        assert_eq!(
+            got,
+            [("closed".to_string(), 80), ("opened".to_string(), 443)]
+        );
+    }
+
+    #[test]
+    fn serves_list_and_refuses_unknown_kills_over_a_socket() {
+        let path = std::env::temp_dir().join(format!("reaper-agent-{}.sock", std::process::id()));
+        let server_path = path.clone();
+        std::thread::spawn(move || listen_on(&server_path, None, &Config::default()));
+        let mut client = (0..50)
+            .find_map(|_| {
+                std::thread::sleep(Duration::from_millis(20));
+                Client::connect(path.clone()).ok()
+            })
+            .unwrap();
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+        client.list().unwrap();
+        // The test process holds no listener: nothing to kill.
+        let me = std::process::id().to_string();
+        let ticks = lsof::starttime_ticks(&me);
+        let err = client.kill(&entry(&me, 1, ticks)).unwrap_err();
+        assert!(err.to_string().contains("holds no listener"), "{}", err);
+        // A refusal isn't a broken connection.
+        assert!(client.error.is_none());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,13 +1,26 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
 use color_eyre::Result;
 use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use ratatui::{
-    DefaultTerminal,
+    Terminal,
+    prelude::CrosstermBackend,
     style::Style,
     text::{Line, Span},
     widgets::TableState,
 };
 
-use crate::lsof::{self, KillOutcome, LsofEntry};
+use crate::audit::{AuditLog, KillRecord, Source};
+use crate::caps::{Cap, Caps};
+use crate::config::Config;
+use crate::history::ResourceHistory;
+use crate::launch::Launch;
+use crate::lsof::{self, EntryId, KillOutcome, LsofEntry, MemoryMetric, PortOutcome, TcpState};
+use crate::protect::{Guard, Protection};
+use crate::sock_diag;
+use crate::stuck::StuckDiagnostics;
+use crate::timeline::Timeline;
 use crate::ui::Colors;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -15,6 +28,45 @@ pub(crate) enum AppMode {
     ProcessList,
     ConfirmKill,
     Search,
+    /// Browsing the selected listener's client connections.
+    Connections,
+    /// "Close this one connection?" — the Connections-mode counterpart of
+    /// ConfirmKill.
+    ConfirmDestroy,
+}
+
+/// What `reaper pick` prints for the chosen row.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum PickField {
+    Pid,
+    Port,
+    /// `ip:port`, ready for curl or nc.
+    Address,
+    /// The whole row, as `reaper agent` sends it.
+    Json,
+}
+
+impl PickField {
+    pub(crate) fn parse(name: &str) -> Option<PickField> {
+        match name {
+            "pid" => Some(PickField::Pid),
+            "port" => Some(PickField::Port),
+            "address" => Some(PickField::Address),
+            "json" => Some(PickField::Json),
+            _ => None,
+        }
+    }
+
+    /// None when the row doesn't have it: a restricted row's pid is
+    /// unknown.
+    fn of(self, p: &LsofEntry) -> Option<String> {
+        match self {
+            PickField::Pid => p.is_killable().then(|| p.pid.clone()),
+            PickField::Port => Some(p.port.to_string()),
+            PickField::Address => Some(std::net::SocketAddr::new(p.ip, p.port).to_string()),
+            PickField::Json => serde_json::to_string(p).ok(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -24,8 +76,11 @@ pub(crate) enum SortBy {
     User,
     Command,
     Memory,
+    Cpu,
     StartTime,
     Protocol,
+    Connections,
+    Backlog,
 }
 
 #[derive(Debug)]
@@ -49,6 +104,38 @@ pub struct App {
     /// index alone could silently come to point at a different process
     /// between "Enter" and "y".
     pub(crate) pending_kill: Option<LsofEntry>,
+    pub(crate) timeline: Timeline,
+    pub(crate) show_timeline: bool,
+    pub(crate) history: ResourceHistory,
+    pub(crate) memory_metric: MemoryMetric,
+    /// Show each process's v4 + v6 sockets on a port as one `TCP4/6` row.
+    pub(crate) merge_dual_stack: bool,
+    /// Cursor into the selected listener's connections (Connections mode).
+    pub(crate) connection_index: usize,
+    /// Snapshot of the connection the ConfirmDestroy prompt is about, for
+    /// the same reason as `pending_kill`.
+    pub(crate) pending_destroy: Option<lsof::Connection>,
+    /// Set when a kill left the process alive; drives the diagnostics
+    /// panel until dismissed with Esc.
+    pub(crate) stuck: Option<StuckDiagnostics>,
+    pub(crate) audit: AuditLog,
+    pub(crate) guard: Guard,
+    /// `guard`'s verdict per (pid, starttime, port), worked out once per
+    /// scan rather than on every frame — unit rules read /proc.
+    pub(crate) protected: HashMap<(String, Option<u64>, u16), Protection>,
+    /// Set when the ConfirmKill prompt is for a rule-protected process:
+    /// the reason shown, with the port number typed so far.
+    pub(crate) pending_protection: Option<(String, String)>,
+    /// Observation only: no kill, restart or connection close is offered
+    /// or performed.
+    pub(crate) read_only: bool,
+    /// reaper's effective capabilities, for saying what exactly is
+    /// missing when a row can't be resolved or killed.
+    pub(crate) caps: Caps,
+    /// Set by `reaper pick`: Enter chooses the row instead of killing it.
+    pub(crate) picking: Option<PickField>,
+    /// The chosen row's field, once Enter was pressed in a pick session.
+    pub(crate) picked: Option<String>,
 }
 
 impl Default for App {
@@ -72,6 +159,22 @@ impl Default for App {
             loading_animation_frame: 0,
             show_restricted: false,
             pending_kill: None,
+            timeline: Timeline::default(),
+            show_timeline: false,
+            history: ResourceHistory::default(),
+            memory_metric: MemoryMetric::Rss,
+            merge_dual_stack: false,
+            connection_index: 0,
+            pending_destroy: None,
+            stuck: None,
+            audit: AuditLog::default(),
+            guard: Guard::default(),
+            protected: HashMap::new(),
+            pending_protection: None,
+            caps: Caps::default(),
+            read_only: false,
+            picking: None,
+            picked: None,
         }
     }
 }
@@ -80,9 +183,16 @@ impl Default for App {
 /// their table-column hues, so the eye picks each piece out instantly.
 /// `verb_color` carries the outcome semantics (green=killed,
 /// orange=force-killed).
-fn kill_status_line(verb: &str, verb_color: ratatui::style::Color, p: &LsofEntry) -> Line<'static> {
+fn kill_status_line(
+    verb: &str,
+    verb_color: ratatui::style::Color,
+    p: &LsofEntry,
+    note: Option<&str>,
+    port: &PortOutcome,
+) -> Line<'static> {
     let dim = Style::default().fg(Colors::TEXT_TERTIARY);
-    Line::from(vec![
+    let warn = Style::default().fg(Colors::WARNING);
+    let mut line = Line::from(vec![
         Span::styled("✓ ", Style::default().fg(Colors::SUCCESS).bold()),
         Span::styled(format!("{} ", verb), Style::default().fg(verb_color).bold()),
         Span::styled(
@@ -93,7 +203,60 @@ fn kill_status_line(verb: &str, verb_color: ratatui::style::Color, p: &LsofEntry
         Span::styled(p.command.clone(), Style::default().fg(Colors::COMMAND_HUE)),
         Span::styled("  pid ", dim),
         Span::styled(p.pid.clone(), Style::default().fg(Colors::PID_HUE).bold()),
-    ])
+    ]);
+    if let Some(note) = note {
+        line.spans.push(Span::styled(format!("  ({})", note), dim));
+    }
+    // The process is gone either way; whether the port is free is what the
+    // user actually wanted to know.
+    line.spans.extend(match port {
+        PortOutcome::PortReleased => vec![Span::styled("  — port released", dim)],
+        PortOutcome::PortStillHeldBy(pid, command) => vec![
+            Span::styled("  — port still held by ", warn),
+            Span::styled(command.clone(), Style::default().fg(Colors::COMMAND_HUE)),
+            Span::styled(format!(" ({}), which inherited the socket", pid), warn),
+        ],
+        PortOutcome::Respawned(pid, command) => vec![
+            Span::styled("  — respawned as ", warn),
+            Span::styled(command.clone(), Style::default().fg(Colors::COMMAND_HUE)),
+            Span::styled(format!(" ({}); something restarts it", pid), warn),
+        ],
+    });
+    line
+}
+
+/// Start `launch` again and describe the result. The kill itself already
+/// succeeded, so a failed spawn is reported as such rather than as a
+/// failed kill.
+fn relaunch_line(launch: &Launch, p: &LsofEntry) -> Line<'static> {
+    let dim = Style::default().fg(Colors::TEXT_TERTIARY);
+    let Some(log) = launch.log_path() else {
+        return kill_failure_line(format!(
+            "Killed {} ({}) but couldn't restart it: neither XDG_STATE_HOME nor HOME is set",
+            p.command, p.pid
+        ));
+    };
+    match launch.spawn(&log) {
+        Ok(pid) => Line::from(vec![
+            Span::styled("✓ ", Style::default().fg(Colors::SUCCESS).bold()),
+            Span::styled("Restarted ", Style::default().fg(Colors::SUCCESS).bold()),
+            Span::styled(
+                format!(":{}", p.port),
+                Style::default().fg(Colors::PORT_HUE).bold(),
+            ),
+            Span::styled("  ", dim),
+            Span::styled(p.command.clone(), Style::default().fg(Colors::COMMAND_HUE)),
+            Span::styled("  pid ", dim),
+            Span::styled(p.pid.clone(), Style::default().fg(Colors::PID_HUE)),
+            Span::styled(" → ", dim),
+            Span::styled(pid.to_string(), Style::default().fg(Colors::PID_HUE).bold()),
+            Span::styled(format!("  (output: {})", log.display()), dim),
+        ]),
+        Err(e) => kill_failure_line(format!(
+            "Killed {} ({}) but couldn't restart it: {}",
+            p.command, p.pid, e
+        )),
+    }
 }
 
 /// A kill that didn't work: red ✗ plus the explanation. Shown in the same
@@ -107,15 +270,40 @@ fn kill_failure_line(message: String) -> Line<'static> {
 }
 
 impl App {
-    pub fn new() -> Self {
+    pub fn new(config: &Config) -> Self {
         Self {
             loading_message: Some("Initializing port scanner...".to_string()),
+            audit: AuditLog::from_config(config),
+            guard: Guard::new(config.protected.clone()),
+            read_only: config.read_only,
+            caps: Caps::effective(),
             ..Default::default()
         }
     }
 
     pub fn refresh_processes(&mut self) {
-        self.processes = self.scanner.scan();
+        let anchor = self.selection_anchor();
+        let peer = self.selected_connection().cloned();
+        // With the agent unreachable, keep the last scan rather than
+        // showing every listener as closed; the header carries the error.
+        let Ok(scan) = self.scanner.try_scan() else {
+            self.loading_message = None;
+            return;
+        };
+        self.processes = scan;
+        self.protected = self
+            .processes
+            .iter()
+            .filter_map(|p| {
+                let key = (p.pid.clone(), p.starttime_ticks, p.port);
+                self.guard.check(p).map(|why| (key, why))
+            })
+            .collect();
+        self.timeline.observe(&self.processes);
+        self.history.record(&self.processes, self.memory_metric);
+        if let Some(stuck) = &mut self.stuck {
+            stuck.refresh();
+        }
         self.apply_filter_and_sort();
 
         if !self.search_query.is_empty()
@@ -127,9 +315,65 @@ impl App {
         }
 
         self.loading_message = None;
-        if self.selected_index >= self.filtered_processes.len() {
-            self.selected_index = 0;
-        }
+        self.restore_selection(anchor);
+        self.restore_connection_cursor(peer);
+    }
+
+    pub(crate) fn selected_connections(&self) -> &[lsof::Connection] {
+        self.filtered_processes
+            .get(self.selected_index)
+            .map(|p| p.connections.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn selected_connection(&self) -> Option<&lsof::Connection> {
+        self.selected_connections().get(self.connection_index)
+    }
+
+    /// Keep the connection cursor on the same peer as connections come
+    /// and go; if it closed, stay at the same position.
+    fn restore_connection_cursor(&mut self, previous: Option<lsof::Connection>) {
+        let connections = self.selected_connections();
+        let found = previous.and_then(|prev| connections.iter().position(|c| c.same_as(&prev)));
+        self.connection_index = match found {
+            Some(i) => i,
+            None => self
+                .connection_index
+                .min(connections.len().saturating_sub(1)),
+        };
+    }
+
+    /// Everything needed to put the cursor back on the same listener
+    /// after the rows are rebuilt: the identities in their current order
+    /// plus where the cursor sits among them.
+    fn selection_anchor(&self) -> (Vec<EntryId>, usize) {
+        let order = self.filtered_processes.iter().map(LsofEntry::id).collect();
+        (order, self.selected_index)
+    }
+
+    /// Re-find the anchored row by identity — never by raw index, which
+    /// silently lands on a different process whenever rows reorder. Only
+    /// when the selected process is gone does the cursor fall back to its
+    /// nearest surviving neighbor in the old ordering (the row below
+    /// first, then the one above, widening outward).
+    fn restore_selection(&mut self, (order, index): (Vec<EntryId>, usize)) {
+        let positions: HashMap<EntryId, usize> = self
+            .filtered_processes
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (p.id(), i))
+            .collect();
+        let lookup = |i: usize| order.get(i).and_then(|id| positions.get(id)).copied();
+
+        let found = lookup(index).or_else(|| {
+            (1..order.len())
+                .find_map(|d| lookup(index + d).or_else(|| index.checked_sub(d).and_then(lookup)))
+        });
+
+        self.selected_index = match found {
+            Some(i) => i,
+            None => index.min(self.filtered_processes.len().saturating_sub(1)),
+        };
         self.table_state
             .select(if self.filtered_processes.is_empty() {
                 None
@@ -138,31 +382,47 @@ impl App {
             });
     }
 
-    pub(crate) fn apply_filter_and_sort(&mut self) {
+    /// Whether a row passes the restricted toggle and the search query.
+    pub(crate) fn matches_filter(&self, p: &LsofEntry) -> bool {
+        if !self.show_restricted && !p.is_killable() {
+            return false;
+        }
         // ASCII case-folding, matching highlight_matching_text in ui.rs,
         // so every row this filter keeps also gets its match underlined.
         let query = self.search_query.to_ascii_lowercase();
-        let has_query = !query.is_empty();
+        if query.is_empty() {
+            return true;
+        }
+        p.command.to_ascii_lowercase().contains(&query)
+            || p.user.to_ascii_lowercase().contains(&query)
+            || p.local_addr.to_ascii_lowercase().contains(&query)
+            || p.port.to_string().contains(&query)
+            || p.pid.contains(&query)
+            || p.cwd
+                .as_deref()
+                .is_some_and(|c| c.to_ascii_lowercase().contains(&query))
+    }
+
+    pub(crate) fn protection(&self, p: &LsofEntry) -> Option<&Protection> {
+        self.protected
+            .get(&(p.pid.clone(), p.starttime_ticks, p.port))
+    }
+
+    /// The scan as the table lists it — with v4/v6 pairs folded together
+    /// when dual-stack merging is on.
+    pub(crate) fn rows(&self) -> Cow<'_, [LsofEntry]> {
+        if self.merge_dual_stack {
+            Cow::Owned(lsof::merge_dual_stack(&self.processes))
+        } else {
+            Cow::Borrowed(&self.processes)
+        }
+    }
 
+    pub(crate) fn apply_filter_and_sort(&mut self) {
         self.filtered_processes = self
-            .processes
+            .rows()
             .iter()
-            .filter(|p| {
-                if !self.show_restricted && !p.is_killable() {
-                    return false;
-                }
-                if !has_query {
-                    return true;
-                }
-                p.command.to_ascii_lowercase().contains(&query)
-                    || p.user.to_ascii_lowercase().contains(&query)
-                    || p.local_addr.to_ascii_lowercase().contains(&query)
-                    || p.port.to_string().contains(&query)
-                    || p.pid.contains(&query)
-                    || p.cwd
-                        .as_deref()
-                        .is_some_and(|c| c.to_ascii_lowercase().contains(&query))
-            })
+            .filter(|p| self.matches_filter(p))
             .cloned()
             .collect();
 
@@ -177,8 +437,13 @@ impl App {
                 SortBy::User => a.user.cmp(&b.user),
                 SortBy::Command => a.command.cmp(&b.command),
                 SortBy::Memory => a
-                    .memory_mb
-                    .partial_cmp(&b.memory_mb)
+                    .memory(self.memory_metric)
+                    .partial_cmp(&b.memory(self.memory_metric))
+                    .unwrap_or(std::cmp::Ordering::Equal),
+                SortBy::Cpu => a
+                    .cpu_percent
+                    .unwrap_or(0.0)
+                    .partial_cmp(&b.cpu_percent.unwrap_or(0.0))
                     .unwrap_or(std::cmp::Ordering::Equal),
                 SortBy::StartTime => match (&a.start_time, &b.start_time) {
                     (Some(at), Some(bt)) => at.cmp(bt),
@@ -187,6 +452,12 @@ impl App {
                     (None, None) => std::cmp::Ordering::Equal,
                 },
                 SortBy::Protocol => a.protocol.cmp(b.protocol),
+                SortBy::Connections => a.connections.len().cmp(&b.connections.len()),
+                SortBy::Backlog => a
+                    .backlog
+                    .fill()
+                    .total_cmp(&b.backlog.fill())
+                    .then(a.backlog.queued.cmp(&b.backlog.queued)),
             };
 
             if self.sort_ascending {
@@ -197,7 +468,10 @@ impl App {
         });
     }
 
-    pub fn run(mut self, mut terminal: DefaultTerminal) -> Result<()> {
+    pub fn run<W: std::io::Write>(
+        &mut self,
+        terminal: &mut Terminal<CrosstermBackend<W>>,
+    ) -> Result<()> {
         use std::time::{Duration, Instant};
         self.running = true;
 
@@ -221,7 +495,7 @@ impl App {
             // is up; when idle, sleep the full stretch to the next refresh
             // instead of waking (and redrawing) ten times a second.
             let until_refresh = refresh_interval.saturating_sub(last_refresh.elapsed());
-            let timeout = if self.mode == AppMode::ConfirmKill {
+            let timeout = if self.is_confirming() {
                 // Refreshes are frozen, so nothing periodic runs — a zero
                 // `until_refresh` here would spin the poll loop. Just wait
                 // for a key in comfortable stretches.
@@ -237,12 +511,12 @@ impl App {
                 needs_redraw = true;
             }
 
-            // Freeze the list while the ConfirmKill prompt is up: the user
+            // Freeze the list while a confirmation prompt is up: the user
             // is deciding based on what's on screen, so nothing may reorder
-            // under them. Paired with the ConfirmKill timeout branch above;
+            // under them. Paired with the confirming timeout branch above;
             // the mode is deliberately re-read here because the key event
             // just handled may have entered or left the prompt.
-            if self.mode != AppMode::ConfirmKill && last_refresh.elapsed() >= refresh_interval {
+            if !self.is_confirming() && last_refresh.elapsed() >= refresh_interval {
                 self.refresh_processes();
                 last_refresh = Instant::now();
                 needs_redraw = true;
@@ -277,14 +551,16 @@ impl App {
             return;
         }
 
-        if self.mode == AppMode::ProcessList {
+        if matches!(self.mode, AppMode::ProcessList | AppMode::Connections) {
             self.status_message = None;
         }
 
         match self.mode {
             AppMode::ProcessList => match (key.modifiers, key.code) {
                 (_, KeyCode::Esc) => {
-                    if !self.search_query.is_empty() {
+                    if self.stuck.is_some() {
+                        self.stuck = None;
+                    } else if !self.search_query.is_empty() {
                         self.search_query.clear();
                         self.apply_filter_and_sort();
                         self.selected_index = 0;
@@ -308,6 +584,9 @@ impl App {
                 (_, KeyCode::Down) => {
                     self.select_next();
                 }
+                (_, KeyCode::Enter) if self.picking.is_some() => {
+                    self.pick_selected();
+                }
                 (_, KeyCode::Enter) => {
                     self.enter_confirm_mode();
                 }
@@ -320,8 +599,23 @@ impl App {
                 (_, KeyCode::Char('a') | KeyCode::Char('A')) => {
                     self.toggle_restricted();
                 }
-                // 1-7 mirror the visual column order: PORT, USER, MEM,
-                // UPTIME, PROTO, PID, COMMAND.
+                (_, KeyCode::Char('c') | KeyCode::Char('C')) => {
+                    self.enter_connections_mode();
+                }
+                (_, KeyCode::Char('d') | KeyCode::Char('D')) => {
+                    self.toggle_dual_stack();
+                }
+                (_, KeyCode::Char('m') | KeyCode::Char('M')) => {
+                    self.cycle_memory_metric();
+                }
+                (_, KeyCode::Char('t') | KeyCode::Char('T')) => {
+                    self.show_timeline = !self.show_timeline;
+                }
+                (_, KeyCode::Char('e') | KeyCode::Char('E')) => {
+                    self.export_launch();
+                }
+                // 1-9 and 0 mirror the visual column order: PORT, USER,
+                // MEM, CPU, UPTIME, PROTO, CONN, BACKLOG, PID, COMMAND.
                 (_, KeyCode::Char('1')) => {
                     self.set_sort(SortBy::Port);
                 }
@@ -332,15 +626,24 @@ impl App {
                     self.set_sort(SortBy::Memory);
                 }
                 (_, KeyCode::Char('4')) => {
-                    self.set_sort(SortBy::StartTime);
+                    self.set_sort(SortBy::Cpu);
                 }
                 (_, KeyCode::Char('5')) => {
-                    self.set_sort(SortBy::Protocol);
+                    self.set_sort(SortBy::StartTime);
                 }
                 (_, KeyCode::Char('6')) => {
-                    self.set_sort(SortBy::Pid);
+                    self.set_sort(SortBy::Protocol);
                 }
                 (_, KeyCode::Char('7')) => {
+                    self.set_sort(SortBy::Connections);
+                }
+                (_, KeyCode::Char('8')) => {
+                    self.set_sort(SortBy::Backlog);
+                }
+                (_, KeyCode::Char('9')) => {
+                    self.set_sort(SortBy::Pid);
+                }
+                (_, KeyCode::Char('0')) => {
                     self.set_sort(SortBy::Command);
                 }
                 (_, KeyCode::Backspace) if !self.search_query.is_empty() => {
@@ -356,13 +659,59 @@ impl App {
                 }
                 _ => {}
             },
+            // A protected process: the port number has to be typed out, so
+            // a reflexive Enter+y can't take down sshd.
+            AppMode::ConfirmKill if self.pending_protection.is_some() => match key.code {
+                KeyCode::Char(c @ '0'..='9') => {
+                    if let Some((_, typed)) = &mut self.pending_protection
+                        && typed.len() < 5
+                    {
+                        typed.push(c);
+                    }
+                }
+                KeyCode::Backspace => {
+                    if let Some((_, typed)) = &mut self.pending_protection {
+                        typed.pop();
+                    }
+                }
+                KeyCode::Enter if self.protected_port_typed() => self.confirm_kill(),
+                KeyCode::Esc => self.cancel_kill(),
+                _ => {}
+            },
             AppMode::ConfirmKill => match (key.modifiers, key.code) {
                 (_, KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter) => {
                     self.confirm_kill()
                 }
+                (_, KeyCode::Char('r') | KeyCode::Char('R')) => self.confirm_restart(),
                 (_, KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc) => self.cancel_kill(),
                 _ => {}
             },
+            AppMode::Connections => match (key.modifiers, key.code) {
+                (_, KeyCode::Esc | KeyCode::Char('c') | KeyCode::Char('C')) => {
+                    self.mode = AppMode::ProcessList;
+                }
+                (_, KeyCode::Char('q')) => self.quit(),
+                (_, KeyCode::Up) => {
+                    self.connection_index = self.connection_index.saturating_sub(1);
+                }
+                (_, KeyCode::Down)
+                    if self.connection_index + 1 < self.selected_connections().len() =>
+                {
+                    self.connection_index += 1;
+                }
+                (_, KeyCode::Char('x') | KeyCode::Char('X')) => self.enter_confirm_destroy(),
+                _ => {}
+            },
+            AppMode::ConfirmDestroy => match (key.modifiers, key.code) {
+                (_, KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter) => {
+                    self.confirm_destroy()
+                }
+                (_, KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc) => {
+                    self.pending_destroy = None;
+                    self.mode = AppMode::Connections;
+                }
+                _ => {}
+            },
             AppMode::Search => match (key.modifiers, key.code) {
                 (_, KeyCode::Esc) => self.exit_search_mode(),
                 (_, KeyCode::Enter) => self.apply_search(),
@@ -419,7 +768,45 @@ impl App {
         }
     }
 
+    fn read_only_refusal(&mut self) {
+        self.status_message = Some(Line::from(vec![
+            Span::styled(
+                "Read-only session",
+                Style::default().fg(Colors::PROTECTED).bold(),
+            ),
+            Span::styled(
+                " — killing and closing connections are disabled",
+                Style::default().fg(Colors::TEXT_TERTIARY),
+            ),
+        ]));
+    }
+
+    fn pick_selected(&mut self) {
+        let (Some(field), Some(selected)) = (
+            self.picking,
+            self.filtered_processes.get(self.selected_index),
+        ) else {
+            return;
+        };
+        match field.of(selected) {
+            Some(value) => {
+                self.picked = Some(value);
+                self.quit();
+            }
+            None => {
+                self.status_message = Some(kill_failure_line(format!(
+                    "The pid behind :{} isn't visible from here; pick another row or another --field",
+                    selected.port
+                )));
+            }
+        }
+    }
+
     fn enter_confirm_mode(&mut self) {
+        if self.read_only {
+            self.read_only_refusal();
+            return;
+        }
         let Some(selected) = self.filtered_processes.get(self.selected_index) else {
             return;
         };
@@ -433,14 +820,48 @@ impl App {
                 ),
                 Span::styled(" — owned by ", dim),
                 Span::styled(selected.user.clone(), Style::default().fg(Colors::USER_HUE)),
-                Span::styled(", re-run with sudo", dim),
+                Span::styled(format!("; {}", self.restricted_hint()), dim),
             ]));
+            // The candidate is a guess from world-readable /proc files;
+            // signalling a guess could hit the wrong process.
+            if let Some(best) = selected.probable.first()
+                && let Some(line) = &mut self.status_message
+            {
+                line.spans.push(Span::styled(
+                    format!(" (probably pid {} — not killed on a guess)", best.pid),
+                    dim,
+                ));
+            }
             return;
         }
+        match self.protection(selected) {
+            Some(Protection::Pinned(why)) => {
+                let dim = Style::default().fg(Colors::TEXT_TERTIARY);
+                self.status_message = Some(Line::from(vec![
+                    Span::styled("Won't kill ", Style::default().fg(Colors::DANGER).bold()),
+                    Span::styled(
+                        format!(":{}", selected.port),
+                        Style::default().fg(Colors::PORT_HUE).bold(),
+                    ),
+                    Span::styled(format!(" — {}", why), dim),
+                ]));
+                return;
+            }
+            Some(rule) => self.pending_protection = Some((rule.reason(), String::new())),
+            None => self.pending_protection = None,
+        }
         self.pending_kill = Some(selected.clone());
         self.mode = AppMode::ConfirmKill;
     }
 
+    fn enter_connections_mode(&mut self) {
+        if self.filtered_processes.get(self.selected_index).is_none() {
+            return;
+        }
+        self.connection_index = 0;
+        self.mode = AppMode::Connections;
+    }
+
     fn enter_search_mode(&mut self) {
         self.mode = AppMode::Search;
     }
@@ -490,19 +911,65 @@ impl App {
         }
     }
 
+    /// The capabilities whose absence leaves rows restricted, if that's
+    /// what does — with them (or a helper) a restricted row is a process
+    /// reaper can't see at all, such as one in another pid namespace.
+    pub(crate) fn restricted_missing_caps(&self) -> Vec<Cap> {
+        // A helper or agent resolves with its own privileges, not ours.
+        if self.scanner.helper.is_some() || self.scanner.agent.is_some() {
+            return Vec::new();
+        }
+        self.caps.missing(&Cap::RESOLVE)
+    }
+
+    fn restricted_hint(&self) -> String {
+        let missing = self.restricted_missing_caps();
+        if missing.is_empty() {
+            "its process isn't visible from here (another pid namespace?)".to_string()
+        } else {
+            format!("resolving its process {}", Caps::remedy(&missing))
+        }
+    }
+
     fn cycle_sort(&mut self) {
         // Cycle follows the visual column order:
-        // PORT → USER → MEM → UPTIME → PROTO → PID → COMMAND → PORT.
+        // PORT → USER → MEM → CPU → UPTIME → PROTO → CONN → BACKLOG → PID →
+        // COMMAND → PORT.
         self.sort_by = match self.sort_by {
             SortBy::Port => SortBy::User,
             SortBy::User => SortBy::Memory,
-            SortBy::Memory => SortBy::StartTime,
+            SortBy::Memory => SortBy::Cpu,
+            SortBy::Cpu => SortBy::StartTime,
             SortBy::StartTime => SortBy::Protocol,
-            SortBy::Protocol => SortBy::Pid,
+            SortBy::Protocol => SortBy::Connections,
+            SortBy::Connections => SortBy::Backlog,
+            SortBy::Backlog => SortBy::Pid,
             SortBy::Pid => SortBy::Command,
             SortBy::Command => SortBy::Port,
         };
+        let anchor = self.selection_anchor();
         self.apply_filter_and_sort();
+        self.restore_selection(anchor);
+    }
+
+    /// Merged rows keep the v4 socket's identity, so the cursor stays put
+    /// when turning merging on; turning it off lands on the v4 half.
+    fn toggle_dual_stack(&mut self) {
+        self.merge_dual_stack = !self.merge_dual_stack;
+        let anchor = self.selection_anchor();
+        self.apply_filter_and_sort();
+        self.restore_selection(anchor);
+    }
+
+    /// RSS → PSS → USS → SWAP. The MEM column, memory sort and trend line
+    /// all follow; the trend restarts so it never mixes two measures.
+    fn cycle_memory_metric(&mut self) {
+        self.memory_metric = self.memory_metric.next();
+        self.history.clear();
+        self.history.record(&self.processes, self.memory_metric);
+        let anchor = self.selection_anchor();
+        self.apply_filter_and_sort();
+        self.restore_selection(anchor);
     }
 
     fn set_sort(&mut self, sort_by: SortBy) {
@@ -512,45 +979,221 @@ impl App {
             self.sort_by = sort_by;
             self.sort_ascending = false;
         }
+        let anchor = self.selection_anchor();
         self.apply_filter_and_sort();
+        self.restore_selection(anchor);
     }
 
     fn confirm_kill(&mut self) {
+        self.kill_pending(false);
+    }
+
+    /// Kill, wait for the port to free up, then start the same command
+    /// again from the same directory with the same environment.
+    fn confirm_restart(&mut self) {
+        self.kill_pending(true);
+    }
+
+    fn kill_pending(&mut self, restart: bool) {
         self.mode = AppMode::ProcessList;
+        // The prompt can't open in read-only mode; this is the backstop.
+        if self.read_only {
+            self.pending_kill = None;
+            return;
+        }
         // Kill the snapshotted process the user actually confirmed — never
         // whatever the current selection index happens to point at.
         let Some(process) = self.pending_kill.take() else {
             return;
         };
+        self.pending_protection = None;
+        // Captured while it's still alive; once it's dead there's nothing
+        // left in /proc to read it from.
+        let launch = if restart {
+            match Launch::capture(&process) {
+                Ok(launch) => Some(launch),
+                Err(e) => {
+                    self.status_message = Some(kill_failure_line(format!(
+                        "Can't restart {} ({}): couldn't read how it was launched: {} — nothing was killed",
+                        process.command, process.pid, e
+                    )));
+                    return;
+                }
+            }
+        } else {
+            None
+        };
 
-        self.status_message =
-            match lsof::kill_process_verified(&process.pid, process.starttime_ticks) {
-                Ok(KillOutcome::Terminated) => {
-                    Some(kill_status_line("Killed", Colors::SUCCESS, &process))
-                }
-                Ok(KillOutcome::ForceKilled) => {
-                    let mut line = kill_status_line("Force-killed", Colors::WARNING, &process);
-                    line.spans.push(Span::styled(
-                        "  (ignored SIGTERM)",
-                        Style::default().fg(Colors::TEXT_TERTIARY),
-                    ));
-                    Some(line)
-                }
-                Ok(KillOutcome::StillAlive) => Some(kill_failure_line(format!(
-                    "{} ({}) is still alive after SIGKILL — likely a kernel-stuck process",
+        let before = lsof::port_inodes(process.port);
+        let outcome = self.scanner.kill_verified(&process);
+        // Through an agent, the agent keeps the record — as the user on
+        // this end of its socket.
+        let audited = if self.scanner.agent.is_some() {
+            Ok(())
+        } else {
+            self.audit
+                .append(&KillRecord::new(Source::Tui, &process, &outcome))
+        };
+        self.status_message = Some(match outcome {
+            Ok(KillOutcome::StillAlive) => {
+                self.stuck = Some(StuckDiagnostics::gather(
+                    &process.pid,
+                    &process.command,
+                    process.starttime_ticks,
+                ));
+                kill_failure_line(format!(
+                    "{} ({}) is still alive after SIGKILL — see the diagnostics below",
                     process.command, process.pid
-                ))),
-                Err(e) => Some(kill_failure_line(format!(
-                    "Failed to signal {} ({}): {}",
-                    process.command, process.pid, e
-                ))),
-            };
+                ))
+            }
+            Err(e) => kill_failure_line(format!(
+                "Failed to signal {} ({}): {}",
+                process.command,
+                process.pid,
+                self.caps.explain_kill_error(&e)
+            )),
+            Ok(done) => {
+                let (verb, color, note) = match done {
+                    KillOutcome::AlreadyExited => ("Already exited", Colors::TEXT_SECONDARY, None),
+                    KillOutcome::Terminated => ("Killed", Colors::SUCCESS, None),
+                    KillOutcome::ForceKilled => {
+                        ("Force-killed", Colors::WARNING, Some("ignored SIGTERM"))
+                    }
+                    KillOutcome::StillAlive => unreachable!("handled above"),
+                };
+                let port = self.scanner.await_port_release(&process, &before);
+                match (&launch, &port) {
+                    (Some(launch), PortOutcome::PortReleased) => relaunch_line(launch, &process),
+                    // Starting a second copy next to an inheritor or a
+                    // supervisor's respawn would just fail to bind.
+                    (Some(_), _) => {
+                        let mut line = kill_status_line(verb, color, &process, note, &port);
+                        line.spans.push(Span::styled(
+                            " — not restarted",
+                            Style::default().fg(Colors::WARNING),
+                        ));
+                        line
+                    }
+                    (None, _) => kill_status_line(verb, color, &process, note, &port),
+                }
+            }
+        });
+        // Say so when the record didn't land — a silent gap in the audit
+        // trail is the one thing it mustn't have.
+        if let (Err(e), Some(line)) = (audited, &mut self.status_message) {
+            line.spans.push(Span::styled(
+                format!("  (not audited: {})", e),
+                Style::default().fg(Colors::WARNING),
+            ));
+        }
+
+        self.refresh_processes();
+    }
 
+    /// Save the selected listener's launch (argv, cwd, environment) as a
+    /// shell script, for restarting it later or elsewhere.
+    fn export_launch(&mut self) {
+        let Some(p) = self.filtered_processes.get(self.selected_index) else {
+            return;
+        };
+        let dim = Style::default().fg(Colors::TEXT_TERTIARY);
+        self.status_message = Some(match Launch::capture(p).and_then(|l| l.export()) {
+            Ok(path) => Line::from(vec![
+                Span::styled("✓ ", Style::default().fg(Colors::SUCCESS).bold()),
+                Span::styled(
+                    "Saved launch of ",
+                    Style::default().fg(Colors::SUCCESS).bold(),
+                ),
+                Span::styled(
+                    format!(":{}", p.port),
+                    Style::default().fg(Colors::PORT_HUE).bold(),
+                ),
+                Span::styled("  ", dim),
+                Span::styled(p.command.clone(), Style::default().fg(Colors::COMMAND_HUE)),
+                Span::styled(format!("  → {}", path.display()), dim),
+            ]),
+            Err(e) => kill_failure_line(format!(
+                "Couldn't export the launch of {} ({}): {}",
+                p.command, p.pid, e
+            )),
+        });
+    }
+
+    fn enter_confirm_destroy(&mut self) {
+        if self.read_only {
+            self.read_only_refusal();
+            return;
+        }
+        let Some(connection) = self.selected_connection() else {
+            return;
+        };
+        // A half-open handshake is the kernel's, not the server's: there's
+        // no socket to abort until accept() would hand one over.
+        if connection.state == TcpState::SynRecv {
+            self.status_message = Some(Line::from(vec![
+                Span::styled(
+                    "Handshake not yet accepted",
+                    Style::default().fg(Colors::WARNING).bold(),
+                ),
+                Span::styled(
+                    format!(
+                        " — {} has no connection to close yet",
+                        connection.remote_display()
+                    ),
+                    Style::default().fg(Colors::TEXT_TERTIARY),
+                ),
+            ]));
+            return;
+        }
+        self.pending_destroy = Some(connection.clone());
+        self.mode = AppMode::ConfirmDestroy;
+    }
+
+    fn confirm_destroy(&mut self) {
+        self.mode = AppMode::Connections;
+        let Some(c) = self.pending_destroy.take() else {
+            return;
+        };
+        if self.read_only {
+            return;
+        }
+        self.status_message = match sock_diag::destroy(c.local(), c.remote(), c.inode) {
+            Ok(()) => Some(Line::from(vec![
+                Span::styled("✓ ", Style::default().fg(Colors::SUCCESS).bold()),
+                Span::styled("Closed ", Style::default().fg(Colors::SUCCESS).bold()),
+                Span::styled(
+                    c.remote_display(),
+                    Style::default().fg(Colors::CONNECTIONS_HUE),
+                ),
+                Span::styled(
+                    format!(" → :{}", c.local_port),
+                    Style::default().fg(Colors::PORT_HUE).bold(),
+                ),
+            ])),
+            Err(e) => Some(kill_failure_line(format!(
+                "Couldn't close {}: {}",
+                c.remote_display(),
+                e
+            ))),
+        };
         self.refresh_processes();
     }
 
+    /// A yes/no prompt is open; the table must hold still under it.
+    fn is_confirming(&self) -> bool {
+        matches!(self.mode, AppMode::ConfirmKill | AppMode::ConfirmDestroy)
+    }
+
+    fn protected_port_typed(&self) -> bool {
+        match (&self.pending_protection, &self.pending_kill) {
+            (Some((_, typed)), Some(p)) => *typed == p.port.to_string(),
+            _ => false,
+        }
+    }
+
     fn cancel_kill(&mut self) {
         self.pending_kill = None;
+        self.pending_protection = None;
         self.mode = AppMode::ProcessList;
     }
 
@@ -558,3 +1201,238 @@ impl App {
         self.running = false;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(pid: &str, port: u16, inode: u64) -> LsofEntry {
+        LsofEntry {
+            command: format!("server-{}", port),
+            ..LsofEntry::test(pid, port, inode)
+        }
+    }
+
+    fn app_with(processes: Vec<LsofEntry>) -> App {
+        let mut app = App {
+            processes,
+            sort_ascending: true,
+            ..Default::default()
+        };
+        app.apply_filter_and_sort();
+        app
+    }
+
+    fn selected_port(app: &App) -> u16 {
+        app.filtered_processes[app.selected_index].port
+    }
+
+    #[test]
+    fn unreachable_agent_keeps_the_last_scan() {
+        let path = std::env::temp_dir().join(format!("reaper-app-{}.sock", std::process::id()));
+        let listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+        let client = crate::agent::Client::connect(path.clone()).unwrap();
+        drop(listener);
+        std::fs::remove_file(&path).unwrap();
+
+        let mut app = app_with(vec![entry("10", 3000, 1)]);
+        app.scanner.agent = Some(client);
+        app.refresh_processes();
+        assert_eq!(app.processes.len(), 1);
+        assert_eq!(app.timeline.events().count(), 0);
+        assert!(app.scanner.agent.as_ref().unwrap().error.is_some());
+    }
+
+    #[test]
+    fn selection_follows_process_across_resort() {
+        let mut app = app_with(vec![
+            entry("10", 3000, 1),
+            entry("20", 8080, 2),
+            entry("30", 5432, 3),
+        ]);
+        app.selected_index = 2; // :8080 in ascending port order
+        app.set_sort(SortBy::Pid);
+        assert_eq!(selected_port(&app), 8080);
+        assert_eq!(app.table_state.selected(), Some(app.selected_index));
+    }
+
+    #[test]
+    fn selection_follows_process_when_rows_appear_above() {
+        let mut app = app_with(vec![entry("10", 3000, 1), entry("20", 8080, 2)]);
+        app.selected_index = 1;
+        let anchor = app.selection_anchor();
+        app.processes.push(entry("30", 22, 3));
+        app.apply_filter_and_sort();
+        app.restore_selection(anchor);
+        assert_eq!(selected_port(&app), 8080);
+    }
+
+    #[test]
+    fn vanished_selection_falls_back_to_nearest_neighbor() {
+        let mut app = app_with(vec![
+            entry("10", 3000, 1),
+            entry("20", 5000, 2),
+            entry("30", 8080, 3),
+        ]);
+        app.selected_index = 1;
+        let anchor = app.selection_anchor();
+        // :5000 dies and a new listener sorts in ahead of everything.
+        app.processes.retain(|p| p.port != 5000);
+        app.processes.push(entry("40", 80, 4));
+        app.apply_filter_and_sort();
+        app.restore_selection(anchor);
+        assert_eq!(selected_port(&app), 8080);
+    }
+
+    #[test]
+    fn dual_stack_toggle_keeps_the_selection() {
+        let mut v6 = entry("20", 8080, 3);
+        v6.protocol = "TCP6";
+        v6.local_addr = "[::]".into();
+        v6.ip = std::net::Ipv6Addr::UNSPECIFIED.into();
+        let mut app = app_with(vec![entry("10", 3000, 1), entry("20", 8080, 2), v6]);
+        app.selected_index = 1;
+        app.toggle_dual_stack();
+        assert_eq!(app.filtered_processes.len(), 2);
+        assert_eq!(
+            app.filtered_processes[app.selected_index].protocol,
+            "TCP4/6"
+        );
+        app.toggle_dual_stack();
+        assert_eq!(app.filtered_processes.len(), 3);
+        assert_eq!(selected_port(&app), 8080);
+    }
+
+    #[test]
+    fn recycled_pid_is_not_the_same_row() {
+        let mut app = app_with(vec![entry("10", 3000, 1), entry("20", 8080, 2)]);
+        app.selected_index = 0;
+        let anchor = app.selection_anchor();
+        // Same pid and port, but a new incarnation bound a new socket.
+        app.processes[0].starttime_ticks = Some(99);
+        app.processes[0].inode = 7;
+        app.apply_filter_and_sort();
+        app.restore_selection(anchor);
+        assert_eq!(selected_port(&app), 8080);
+    }
+
+    fn peer(remote_port: u16) -> lsof::Connection {
+        lsof::Connection {
+            local_ip: std::net::Ipv4Addr::LOCALHOST.into(),
+            local_port: 3000,
+            remote_ip: std::net::Ipv4Addr::LOCALHOST.into(),
+            remote_port,
+            state: lsof::TcpState::Established,
+            inode: remote_port as u64,
+        }
+    }
+
+    #[test]
+    fn connection_cursor_follows_its_peer() {
+        let mut listener = entry("10", 3000, 1);
+        listener.connections = vec![peer(40001), peer(40002), peer(40003)];
+        let mut app = app_with(vec![listener]);
+        app.connection_index = 2;
+        let previous = app.selected_connection().cloned();
+
+        // The first client hung up; the selected one moved up a slot.
+        app.processes[0].connections.remove(0);
+        app.apply_filter_and_sort();
+        app.restore_connection_cursor(previous.clone());
+        assert_eq!(app.selected_connection().unwrap().remote_port, 40003);
+
+        // Now it's gone too: stay in range rather than point past the end.
+        app.processes[0].connections.pop();
+        app.apply_filter_and_sort();
+        app.restore_connection_cursor(previous);
+        assert_eq!(app.selected_connection().unwrap().remote_port, 40002);
+    }
+
+    #[test]
+    fn read_only_never_opens_a_kill_or_close_prompt() {
+        let mut listener = entry("10", 3000, 1);
+        listener.connections = vec![peer(40001)];
+        let mut app = app_with(vec![listener]);
+        app.read_only = true;
+        let press = |app: &mut App, code| app.on_key_event(KeyEvent::new(code, KeyModifiers::NONE));
+
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.mode, AppMode::ProcessList);
+        assert!(app.pending_kill.is_none());
+
+        press(&mut app, KeyCode::Char('c'));
+        press(&mut app, KeyCode::Char('x'));
+        assert_eq!(app.mode, AppMode::Connections);
+        assert!(app.pending_destroy.is_none());
+    }
+
+    #[test]
+    fn a_half_open_handshake_is_not_offered_for_closing() {
+        let mut listener = entry("10", 3000, 1);
+        let mut half_open = peer(40001);
+        half_open.state = lsof::TcpState::SynRecv;
+        half_open.inode = 0;
+        listener.connections = vec![half_open, peer(40002)];
+        let mut app = app_with(vec![listener]);
+        let press = |app: &mut App, code| app.on_key_event(KeyEvent::new(code, KeyModifiers::NONE));
+
+        press(&mut app, KeyCode::Char('c'));
+        app.connection_index = app.filtered_processes[0]
+            .connections
+            .iter()
+            .position(|c| c.state == lsof::TcpState::SynRecv)
+            .unwrap();
+        press(&mut app, KeyCode::Char('x'));
+        assert_eq!(app.mode, AppMode::Connections);
+        assert!(app.pending_destroy.is_none());
+    }
+
+    #[test]
+    fn picking_returns_the_selected_field_instead_of_killing() {
+        let mut app = app_with(vec![entry("10", 3000, 1), entry("?", 5432, 2)]);
+        app.show_restricted = true;
+        app.apply_filter_and_sort();
+        app.picking = Some(PickField::Pid);
+        app.read_only = true;
+        app.running = true;
+        while selected_port(&app) != 5432 {
+            app.select_next();
+        }
+
+        // No pid to give for a restricted row: say so and stay.
+        app.on_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert!(app.running);
+        assert_eq!(app.picked, None);
+
+        app.picking = Some(PickField::Address);
+        app.on_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert!(!app.running);
+        assert_eq!(app.mode, AppMode::ProcessList);
+        assert_eq!(app.picked.as_deref(), Some("0.0.0.0:5432"));
+    }
+
+    #[test]
+    fn a_probable_owner_is_never_offered_for_killing() {
+        let mut restricted = entry("?", 5432, 1);
+        restricted.probable = vec![lsof::Candidate {
+            pid: "812".into(),
+            starttime_ticks: Some(1),
+            command: "postgres -D /var/lib/pg".into(),
+            hint: "its command line mentions 5432".into(),
+        }];
+        let mut app = app_with(vec![restricted]);
+        app.show_restricted = true;
+        app.apply_filter_and_sort();
+        app.on_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(app.mode, AppMode::ProcessList);
+        assert!(app.pending_kill.is_none());
+        let status: String = app
+            .status_message
+            .unwrap()
+            .spans
+            .iter()
+            .map(|s| s.content.as_ref())
+            .collect();
+        assert!(status.contains("probably pid 812"), "{}", status);
+    }
+}
@@ -0,0 +1,346 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::net::UnixDatagram;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::caps;
+use crate::config::{self, Config};
+use crate::lsof::{self, KillOutcome, LsofEntry, PartialKill};
+
+/// Where a kill was issued from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Source {
+    Tui,
+    Cli,
+    /// A client of `reaper agent`; `user` is then the client's.
+    Agent,
+    /// A request to `reaper helper`; `user` is then the requester's.
+    Helper,
+}
+
+/// One line of kills.jsonl: who killed what, when, and how it went.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct KillRecord {
+    /// Seconds since the Unix epoch.
+    pub(crate) timestamp: u64,
+    /// The user reaper ran as — for agent kills, the client's user.
+    pub(crate) user: String,
+    /// Who invoked sudo, when reaper ran under it — on a shared box
+    /// `user` alone would just say "root".
+    pub(crate) sudo_user: Option<String>,
+    pub(crate) source: Source,
+    pub(crate) pid: String,
+    pub(crate) starttime_ticks: Option<u64>,
+    pub(crate) command: String,
+    pub(crate) cwd: Option<String>,
+    pub(crate) port: u16,
+    pub(crate) address: String,
+    pub(crate) protocol: String,
+    pub(crate) signals: Vec<String>,
+    /// `KillOutcome::label()`, or `error` with `error` set.
+    pub(crate) outcome: String,
+    pub(crate) error: Option<String>,
+}
+
+impl KillRecord {
+    pub(crate) fn new(
+        source: Source,
+        p: &LsofEntry,
+        outcome: &io::Result<KillOutcome>,
+    ) -> KillRecord {
+        let (signals, label, error) = match outcome {
+            Ok(o) => (o.signals_sent(), o.label(), None),
+            Err(e) => (PartialKill::signals_in(e), "error", Some(e.to_string())),
+        };
+        KillRecord {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            user: lsof::user_name(unsafe { libc::getuid() }),
+            sudo_user: std::env::var("SUDO_USER").ok().filter(|u| !u.is_empty()),
+            source,
+            pid: p.pid.clone(),
+            starttime_ticks: p.starttime_ticks,
+            command: p.command.clone(),
+            cwd: p.cwd.clone(),
+            port: p.port,
+            address: p.local_addr.clone(),
+            protocol: p.protocol.to_string(),
+            signals: signals.iter().map(|s| s.to_string()).collect(),
+            outcome: label.to_string(),
+            error,
+        }
+    }
+
+    /// `root (sudo from bob)` or just `alice`.
+    pub(crate) fn who(&self) -> String {
+        match &self.sudo_user {
+            Some(sudo) => format!("{} (sudo from {})", self.user, sudo),
+            None => self.user.clone(),
+        }
+    }
+
+    /// One-line summary for syslog.
+    fn syslog_message(&self) -> String {
+        let mut msg = format!(
+            "kill by {} via {}: pid={} command={:?} port={} address={} signals={} outcome={}",
+            self.who(),
+            match self.source {
+                Source::Tui => "tui",
+                Source::Cli => "cli",
+                Source::Agent => "agent",
+                Source::Helper => "helper",
+            },
+            self.pid,
+            self.command,
+            self.port,
+            self.address,
+            if self.signals.is_empty() {
+                "none".to_string()
+            } else {
+                self.signals.join(",")
+            },
+            self.outcome
+        );
+        if let Some(e) = &self.error {
+            msg.push_str(&format!(" error={:?}", e));
+        }
+        msg
+    }
+}
+
+/// Appends kill records to `$XDG_STATE_HOME/reaper/kills.jsonl` and,
+/// when configured, to syslog. The default writes nowhere — tests and
+/// other throwaway `App`s never touch the real log.
+#[derive(Debug, Default)]
+pub(crate) struct AuditLog {
+    path: Option<PathBuf>,
+    syslog: bool,
+}
+
+/// LOG_AUTH | LOG_NOTICE: who-did-what records, like sudo's own.
+const SYSLOG_PRIORITY: u8 = 4 * 8 + 5;
+
+/// Where the root helper keeps its own record. Fixed and root-owned: the
+/// XDG variables it inherits through sudo are the asking user's.
+const SYSTEM_LOG: &str = "/var/log/reaper/kills.jsonl";
+
+/// The user's own log — or, when file capabilities let this process
+/// kill for others, the system one: that record isn't the user's to
+/// point elsewhere through XDG_STATE_HOME.
+pub(crate) fn log_path() -> Option<PathBuf> {
+    if caps::elevated_by_file() {
+        return Some(PathBuf::from(SYSTEM_LOG));
+    }
+    config::state_dir().map(|d| d.join("kills.jsonl"))
+}
+
+impl AuditLog {
+    pub(crate) fn from_config(config: &Config) -> AuditLog {
+        AuditLog {
+            path: log_path(),
+            syslog: config.syslog,
+        }
+    }
+
+    pub(crate) fn system() -> AuditLog {
+        AuditLog {
+            path: Some(PathBuf::from(SYSTEM_LOG)),
+            syslog: false,
+        }
+    }
+
+    /// Write `record` everywhere it should go. Errors are returned, not
+    /// swallowed: an audit trail that silently stops is worse than none.
+    pub(crate) fn append(&self, record: &KillRecord) -> io::Result<()> {
+        if let Some(path) = &self.path {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let mut line = serde_json::to_string(record).map_err(io::Error::other)?;
+            line.push('\n');
+            // One write of one line with O_APPEND, so concurrent reapers
+            // can't interleave records.
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .mode(0o600)
+                .open(path)?
+                .write_all(line.as_bytes())?;
+        }
+        if self.syslog {
+            let message = format!(
+                "<{}>reaper[{}]: {}",
+                SYSLOG_PRIORITY,
+                std::process::id(),
+                record.syslog_message()
+            );
+            UnixDatagram::unbound()?.send_to(message.as_bytes(), "/dev/log")?;
+        }
+        Ok(())
+    }
+}
+
+/// Every record in the log, oldest first. Lines that don't parse (a
+/// truncated write, a hand edit) are skipped rather than failing the lot.
+pub(crate) fn read_log(path: &PathBuf) -> io::Result<Vec<KillRecord>> {
+    let file = match fs::File::open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    Ok(BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect())
+}
+
+/// `reaper history` filters; every set field must match.
+#[derive(Debug, Default)]
+pub(crate) struct Filter {
+    pub(crate) port: Option<u16>,
+    /// Matches the user reaper ran as or the sudo user behind it.
+    pub(crate) user: Option<String>,
+    /// Substring of the command line.
+    pub(crate) command: Option<String>,
+    /// Only records at or after this Unix time.
+    pub(crate) since: Option<u64>,
+}
+
+impl Filter {
+    pub(crate) fn matches(&self, r: &KillRecord) -> bool {
+        self.port.is_none_or(|p| r.port == p)
+            && self
+                .user
+                .as_ref()
+                .is_none_or(|u| &r.user == u || r.sudo_user.as_ref() == Some(u))
+            && self
+                .command
+                .as_ref()
+                .is_none_or(|c| r.command.contains(c.as_str()))
+            && self.since.is_none_or(|t| r.timestamp >= t)
+    }
+}
+
+/// `2026-10-18 14:03:22` in the local timezone.
+pub(crate) fn format_timestamp(secs: u64) -> String {
+    let t = secs as libc::time_t;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&t, &mut tm) }.is_null() {
+        return secs.to_string();
+    }
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(port: u16, user: &str, sudo_user: Option<&str>, command: &str) -> KillRecord {
+        KillRecord {
+            timestamp: 1_700_000_000,
+            user: user.into(),
+            sudo_user: sudo_user.map(Into::into),
+            source: Source::Tui,
+            pid: "4242".into(),
+            starttime_ticks: Some(123),
+            command: command.into(),
+            cwd: Some("/srv/app".into()),
+            port,
+            address: "*".into(),
+            protocol: "TCP".into(),
+            signals: vec!["SIGTERM".into()],
+            outcome: "terminated".into(),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn records_round_trip_through_the_log() {
+        let dir = std::env::temp_dir().join(format!("reaper-audit-{}", std::process::id()));
+        let path = dir.join("kills.jsonl");
+        let log = AuditLog {
+            path: Some(path.clone()),
+            syslog: false,
+        };
+        let a = record(3000, "alice", None, "node server.js");
+        let b = record(5432, "root", Some("bob"), "postgres");
+        log.append(&a).unwrap();
+        log.append(&b).unwrap();
+        // A torn last line doesn't hide the good ones.
+        OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"{\"timestamp\":")
+            .unwrap();
+        assert_eq!(read_log(&path).unwrap(), vec![a, b]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn filter_matches_sudo_user_too() {
+        let r = record(5432, "root", Some("bob"), "postgres -D /var/lib/pg");
+        let by = |f: Filter| f.matches(&r);
+        assert!(by(Filter::default()));
+        assert!(by(Filter {
+            user: Some("bob".into()),
+            ..Default::default()
+        }));
+        assert!(by(Filter {
+            port: Some(5432),
+            command: Some("postgres".into()),
+            ..Default::default()
+        }));
+        assert!(!by(Filter {
+            port: Some(3000),
+            ..Default::default()
+        }));
+        assert!(!by(Filter {
+            since: Some(1_800_000_000),
+            ..Default::default()
+        }));
+    }
+
+    #[test]
+    fn error_outcomes_record_only_the_signals_sent() {
+        let p = LsofEntry {
+            command: "node".into(),
+            ..LsofEntry::test("10", 3000, 1)
+        };
+        let r = KillRecord::new(Source::Cli, &p, &Err(io::Error::other("recycled")));
+        assert_eq!(r.outcome, "error");
+        assert!(r.signals.is_empty());
+        let r = KillRecord::new(Source::Cli, &p, &Ok(KillOutcome::ForceKilled));
+        assert_eq!(r.signals, ["SIGTERM", "SIGKILL"]);
+
+        // SIGTERM went out, SIGKILL couldn't: the record says so.
+        let refused = io::Error::from_raw_os_error(libc::EPERM);
+        let r = KillRecord::new(Source::Cli, &p, &PartialKill::after_sigterm(refused));
+        assert_eq!(
+            (r.outcome.as_str(), &r.signals[..]),
+            ("error", &["SIGTERM".to_string()][..])
+        );
+        // It exited just before SIGKILL: SIGTERM did the job.
+        let gone = io::Error::from_raw_os_error(libc::ESRCH);
+        assert_eq!(
+            PartialKill::after_sigterm(gone).unwrap(),
+            KillOutcome::Terminated
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use std::fs;
+
+/// The Linux capabilities reaper can make use of, with what each buys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Cap {
+    /// List other users' /proc/<pid>/fd directories.
+    DacReadSearch,
+    /// Read where the links in them point. With DacReadSearch, how a
+    /// socket inode is traced back to its process.
+    SysPtrace,
+    /// Signal other users' processes.
+    Kill,
+    /// SOCK_DESTROY, for closing a single connection.
+    NetAdmin,
+}
+
+impl Cap {
+    pub(crate) const ALL: [Cap; 4] = [Cap::DacReadSearch, Cap::SysPtrace, Cap::Kill, Cap::NetAdmin];
+    /// Both are needed to see which process owns another user's socket.
+    pub(crate) const RESOLVE: [Cap; 2] = [Cap::DacReadSearch, Cap::SysPtrace];
+
+    fn bit(self) -> u32 {
+        match self {
+            Cap::DacReadSearch => 2,
+            Cap::Kill => 5,
+            Cap::NetAdmin => 12,
+            Cap::SysPtrace => 19,
+        }
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Cap::DacReadSearch => "CAP_DAC_READ_SEARCH",
+            Cap::SysPtrace => "CAP_SYS_PTRACE",
+            Cap::Kill => "CAP_KILL",
+            Cap::NetAdmin => "CAP_NET_ADMIN",
+        }
+    }
+
+    /// The spelling setcap(8) takes.
+    pub(crate) fn setcap_name(self) -> String {
+        self.name().to_ascii_lowercase()
+    }
+
+    pub(crate) fn purpose(self) -> &'static str {
+        match self {
+            Cap::DacReadSearch => "listing another user's open files",
+            Cap::SysPtrace => "seeing which of them is a listening socket",
+            Cap::Kill => "killing another user's process",
+            Cap::NetAdmin => "closing a single connection (x)",
+        }
+    }
+}
+
+/// A capability set, as the hex masks in /proc/<pid>/status.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Caps(u64);
+
+fn parse_mask(status: &str, field: &str) -> Option<Caps> {
+    status
+        .lines()
+        .find_map(|l| l.strip_prefix(field)?.strip_prefix(':'))
+        .and_then(|v| u64::from_str_radix(v.trim(), 16).ok())
+        .map(Caps)
+}
+
+impl Caps {
+    /// reaper's effective set. Root normally has every bit; a binary
+    /// given file capabilities has just those.
+    pub(crate) fn effective() -> Caps {
+        Caps::read("CapEff")
+    }
+
+    /// The bounding set: what this process could ever gain, e.g. by
+    /// exec'ing a binary with file capabilities. Containers trim it.
+    pub(crate) fn bounding() -> Caps {
+        Caps::read("CapBnd")
+    }
+
+    fn read(field: &str) -> Caps {
+        fs::read_to_string("/proc/self/status")
+            .ok()
+            .and_then(|s| parse_mask(&s, field))
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn has(self, cap: Cap) -> bool {
+        self.0 & (1 << cap.bit()) != 0
+    }
+
+    /// Those of `caps` this set lacks.
+    pub(crate) fn missing(self, caps: &[Cap]) -> Vec<Cap> {
+        caps.iter().copied().filter(|c| !self.has(*c)).collect()
+    }
+
+    /// What's needed and where to get it: "needs CAP_KILL — sudo, …".
+    pub(crate) fn remedy(caps: &[Cap]) -> String {
+        let names: Vec<_> = caps.iter().map(|c| c.name()).collect();
+        format!(
+            "needs {} — sudo, --elevate, or see `reaper doctor`",
+            names.join(" and ")
+        )
+    }
+
+    /// A failed kill's error, plus the remedy when the failure is the
+    /// missing CAP_KILL rather than something a capability can't fix.
+    pub(crate) fn explain_kill_error(self, e: &std::io::Error) -> String {
+        if e.kind() == std::io::ErrorKind::PermissionDenied && !self.has(Cap::Kill) {
+            format!("{} ({})", e, Caps::remedy(&[Cap::Kill]))
+        } else {
+            e.to_string()
+        }
+    }
+}
+
+/// Whether reaper holds capabilities its user doesn't — a non-root user
+/// running a binary given file capabilities. It then mustn't hand that
+/// user what the capabilities let it read, nor go by files they write.
+pub(crate) fn elevated_by_file() -> bool {
+    let uid = unsafe { libc::getuid() };
+    uid != 0 && Caps::effective() != Caps::default()
+}
+
+/// The permitted set and effective flag of a file's `security.capability`
+/// attribute (struct vfs_cap_data, revisions 2 and 3), or None if the
+/// file has none.
+pub(crate) fn file_caps(path: &std::path::Path) -> Option<(Caps, bool)> {
+    use std::os::unix::ffi::OsStrExt;
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut buf = [0u8; 24];
+    let n = unsafe {
+        libc::getxattr(
+            path.as_ptr(),
+            c"security.capability".as_ptr(),
+            buf.as_mut_ptr().cast(),
+            buf.len(),
+        )
+    };
+    parse_vfs_cap(buf.get(..usize::try_from(n).ok()?)?)
+}
+
+fn parse_vfs_cap(data: &[u8]) -> Option<(Caps, bool)> {
+    let word = |i: usize| -> Option<u64> {
+        let b = data.get(i * 4..i * 4 + 4)?;
+        Some(u32::from_le_bytes(b.try_into().ok()?) as u64)
+    };
+    let magic = word(0)?;
+    // magic_etc, then (permitted, inheritable) pairs, low word first.
+    let permitted = word(1)? | word(3).unwrap_or(0) << 32;
+    Some((Caps(permitted), magic & 1 != 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_masks_from_status() {
+        let status = "Name:\treaper\nCapPrm:\t0000000000080020\nCapEff:\t0000000000080020\nCapBnd:\t000001ffffffffff\n";
+        let eff = parse_mask(status, "CapEff").unwrap();
+        assert!(eff.has(Cap::SysPtrace));
+        assert!(eff.has(Cap::Kill));
+        assert!(!eff.has(Cap::NetAdmin));
+        assert_eq!(eff.missing(&Cap::RESOLVE), [Cap::DacReadSearch]);
+        // Only whole field names match.
+        assert_eq!(parse_mask(status, "Cap"), None);
+        assert!(
+            Cap::ALL
+                .iter()
+                .all(|c| parse_mask(status, "CapBnd").unwrap().has(*c))
+        );
+    }
+
+    #[test]
+    fn decodes_file_capabilities() {
+        // What `setcap cap_kill,cap_sys_ptrace+ep` stores: revision 2,
+        // effective flag set.
+        let mut data = Vec::new();
+        for word in [0x0200_0001u32, 0x0008_0020, 0, 0, 0] {
+            data.extend_from_slice(&word.to_le_bytes());
+        }
+        let (caps, effective) = parse_vfs_cap(&data).unwrap();
+        assert!(effective);
+        assert!(caps.has(Cap::Kill) && caps.has(Cap::SysPtrace));
+        assert!(!caps.has(Cap::NetAdmin));
+        assert_eq!(parse_vfs_cap(&[1, 2]), None);
+    }
+}
@@ -0,0 +1,403 @@
+use std::collections::HashSet;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::audit::{self, AuditLog, Filter, KillRecord, Source};
+use crate::caps::{self, Cap, Caps};
+use crate::config::Config;
+use crate::lsof::{self, KillOutcome, PortOutcome};
+use crate::protect::{Guard, Protection};
+
+/// A bad command line: printed with the usage and exit code 2, the same
+/// way `main` treats an unknown option.
+pub(crate) struct UsageError(pub(crate) String);
+
+fn value<'a>(
+    flag: &str,
+    args: &mut impl Iterator<Item = &'a String>,
+) -> Result<&'a String, UsageError> {
+    args.next()
+        .ok_or_else(|| UsageError(format!("{} needs a value", flag)))
+}
+
+fn parse_port(s: &str) -> Result<u16, UsageError> {
+    s.parse()
+        .map_err(|_| UsageError(format!("not a port number: {}", s)))
+}
+
+/// `90s`, `30m`, `12h`, `7d` → seconds.
+fn parse_age(s: &str) -> Option<u64> {
+    let unit = match s.chars().last()? {
+        's' => 1,
+        'm' => 60,
+        'h' => 3600,
+        'd' => 86_400,
+        _ => return None,
+    };
+    s[..s.len() - 1].parse::<u64>().ok().map(|n| n * unit)
+}
+
+/// `reaper history [--port P] [--user U] [--grep TEXT] [--since AGE]
+/// [--last N] [--json]`
+pub(crate) fn history(args: &[String]) -> Result<(), UsageError> {
+    let mut filter = Filter::default();
+    let mut last = None;
+    let mut json = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => filter.port = Some(parse_port(value(arg, &mut args)?)?),
+            "--user" => filter.user = Some(value(arg, &mut args)?.clone()),
+            "--grep" => filter.command = Some(value(arg, &mut args)?.clone()),
+            "--since" => {
+                let age = value(arg, &mut args)?;
+                let secs = parse_age(age).ok_or_else(|| {
+                    UsageError(format!("--since expects e.g. 30m, 12h or 7d, got {}", age))
+                })?;
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |d| d.as_secs());
+                filter.since = Some(now.saturating_sub(secs));
+            }
+            "--last" => {
+                let n = value(arg, &mut args)?;
+                last = Some(
+                    n.parse::<usize>()
+                        .map_err(|_| UsageError(format!("--last expects a count, got {}", n)))?,
+                );
+            }
+            "--json" => json = true,
+            other => return Err(UsageError(format!("unknown history option: {}", other))),
+        }
+    }
+
+    let Some(path) = audit::log_path() else {
+        eprintln!("no state directory: neither XDG_STATE_HOME nor HOME is set");
+        std::process::exit(1);
+    };
+    let records = audit::read_log(&path).unwrap_or_else(|e| {
+        eprintln!("{}: {}", path.display(), e);
+        std::process::exit(1);
+    });
+    let mut records: Vec<&KillRecord> = records.iter().filter(|r| filter.matches(r)).collect();
+    if let Some(n) = last {
+        records.drain(..records.len().saturating_sub(n));
+    }
+
+    if json {
+        for r in records {
+            println!("{}", serde_json::to_string(r).unwrap_or_default());
+        }
+        return Ok(());
+    }
+    if records.is_empty() {
+        eprintln!("no matching kills in {}", path.display());
+        return Ok(());
+    }
+    println!(
+        "{:<19}  {:<20}  {:<6}  {:>7}  {:>5}  {:<13}  {:<15}  COMMAND",
+        "TIME", "WHO", "VIA", "PID", "PORT", "OUTCOME", "SIGNALS"
+    );
+    for r in records {
+        let mut outcome = r.outcome.clone();
+        if let Some(e) = &r.error {
+            outcome = format!("{} ({})", outcome, e);
+        }
+        println!(
+            "{:<19}  {:<20}  {:<6}  {:>7}  {:>5}  {:<13}  {:<15}  {}",
+            audit::format_timestamp(r.timestamp),
+            r.who(),
+            match r.source {
+                Source::Tui => "tui",
+                Source::Cli => "cli",
+                Source::Agent => "agent",
+                Source::Helper => "helper",
+            },
+            r.pid,
+            r.port,
+            outcome,
+            if r.signals.is_empty() {
+                "-".to_string()
+            } else {
+                r.signals.join(",")
+            },
+            r.command
+        );
+    }
+    Ok(())
+}
+
+/// `reaper kill <PORT> [-y]`: the TUI's Enter+y without the TUI. Every
+/// killable process on the port gets the same starttime-verified kill and
+/// an audit record. Exits 1 when nothing was killed.
+pub(crate) fn kill(args: &[String], config: &Config) -> Result<(), UsageError> {
+    let mut port = None;
+    let mut yes = false;
+    for arg in args {
+        match arg.as_str() {
+            "-y" | "--yes" => yes = true,
+            other if port.is_none() => port = Some(parse_port(other)?),
+            other => return Err(UsageError(format!("unexpected argument: {}", other))),
+        }
+    }
+    let Some(port) = port else {
+        return Err(UsageError("kill needs a port".to_string()));
+    };
+    if config.read_only {
+        eprintln!("read_only is set in config.toml; not killing anything");
+        std::process::exit(1);
+    }
+
+    let mut scanner = lsof::Scanner::default();
+    let scan = scanner.scan();
+    let on_port: Vec<_> = scan.iter().filter(|p| p.port == port).collect();
+    if on_port.is_empty() {
+        eprintln!("nothing is listening on :{}", port);
+        std::process::exit(1);
+    }
+    // v4 + v6 (or several addresses) of one process: one kill, one record.
+    let mut seen = HashSet::new();
+    let targets: Vec<_> = on_port
+        .iter()
+        .filter(|p| p.is_killable() && seen.insert((p.pid.clone(), p.starttime_ticks)))
+        .copied()
+        .collect();
+    if targets.is_empty() {
+        let missing = Caps::effective().missing(&Cap::RESOLVE);
+        if missing.is_empty() {
+            eprintln!(
+                ":{} is held by a process that isn't visible from here (another pid namespace?)",
+                port
+            );
+        } else {
+            eprintln!(
+                ":{} is held by a process you can't see; seeing it {}",
+                port,
+                Caps::remedy(&missing)
+            );
+        }
+        std::process::exit(1);
+    }
+
+    // Same rules as the TUI: pinned processes are never killed, rule-
+    // protected ones only with the port typed out at the prompt — which
+    // `-y` deliberately can't stand in for.
+    let guard = Guard::new(config.protected.clone());
+    let mut typed_confirmation = false;
+    let targets: Vec<_> = targets
+        .into_iter()
+        .filter(|p| match guard.check(p) {
+            None => true,
+            Some(Protection::Pinned(why)) => {
+                eprintln!("won't kill {} (pid {}): {}", p.command, p.pid, why);
+                false
+            }
+            Some(rule) if yes => {
+                eprintln!(
+                    "won't kill {} (pid {}) with -y: {}; run without -y to confirm",
+                    p.command,
+                    p.pid,
+                    rule.reason()
+                );
+                false
+            }
+            Some(rule) => {
+                eprintln!("{} (pid {}) is {}", p.command, p.pid, rule.reason());
+                typed_confirmation = true;
+                true
+            }
+        })
+        .collect();
+    if targets.is_empty() {
+        std::process::exit(1);
+    }
+
+    for p in &targets {
+        eprintln!(
+            "{} (pid {}, {}) on {}:{}",
+            p.command, p.pid, p.user, p.local_addr, p.port
+        );
+    }
+    if !yes {
+        // Never block a script on a prompt nobody can answer.
+        if !io::stdin().is_terminal() {
+            eprintln!("refusing to kill without a terminal to confirm on; pass -y");
+            std::process::exit(1);
+        }
+        let them = if targets.len() == 1 { "it" } else { "them" };
+        if typed_confirmation {
+            eprint!("Type the port number to kill {}: ", them);
+        } else {
+            eprint!("Kill {}? [y/N] ", them);
+        }
+        io::stderr().flush().ok();
+        let mut answer = String::new();
+        io::stdin().lock().read_line(&mut answer).ok();
+        let confirmed = if typed_confirmation {
+            answer.trim() == port.to_string()
+        } else {
+            matches!(answer.trim(), "y" | "Y" | "yes")
+        };
+        if !confirmed {
+            eprintln!("cancelled");
+            std::process::exit(1);
+        }
+    }
+
+    let audit = AuditLog::from_config(config);
+    let mut failed = false;
+    for p in targets {
+        let before = lsof::port_inodes(p.port);
+        let outcome = scanner.kill_verified(p);
+        if let Err(e) = audit.append(&KillRecord::new(Source::Cli, p, &outcome)) {
+            eprintln!("warning: kill not audited: {}", e);
+        }
+        let verb = match &outcome {
+            Ok(KillOutcome::AlreadyExited) => "already exited",
+            Ok(KillOutcome::Terminated) => "killed",
+            Ok(KillOutcome::ForceKilled) => "force-killed (ignored SIGTERM)",
+            Ok(KillOutcome::StillAlive) => {
+                failed = true;
+                println!(
+                    "✗ {} ({}) is still alive after SIGKILL — run reaper to see why",
+                    p.command, p.pid
+                );
+                continue;
+            }
+            Err(e) => {
+                failed = true;
+                println!(
+                    "✗ failed to signal {} ({}): {}",
+                    p.command,
+                    p.pid,
+                    Caps::effective().explain_kill_error(e)
+                );
+                continue;
+            }
+        };
+        let port = match scanner.await_port_release(p, &before) {
+            PortOutcome::PortReleased => "port released".to_string(),
+            PortOutcome::PortStillHeldBy(pid, command) => format!(
+                "port still held by {} ({}), which inherited the socket",
+                command, pid
+            ),
+            PortOutcome::Respawned(pid, command) => {
+                format!("respawned as {} ({}); something restarts it", command, pid)
+            }
+        };
+        println!(
+            "✓ {} :{} {} pid {} — {}",
+            verb, p.port, p.command, p.pid, port
+        );
+    }
+    if failed {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// What reaper holds back when it runs with capabilities its user lacks.
+const FILE_CAPS_LIMITS: &str = "With file capabilities, reaper won't read or export (e) the environment of another user's process, nor restart (r) one, and it goes by /etc/reaper/protected alone, ignoring ~/.config/reaper/protected. Kills are audited to /var/log/reaper/kills.jsonl, not your own log: create it writable by whoever may run this binary, or kills go unrecorded with a warning.";
+
+/// `reaper doctor`: which capabilities reaper has, what each is for, and
+/// how to grant just those to the binary instead of running it as root.
+pub(crate) fn doctor() -> Result<(), UsageError> {
+    let exe = std::env::current_exe()
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|_| "reaper".to_string());
+    let uid = unsafe { libc::getuid() };
+    println!(
+        "reaper {} at {}, running as {} (uid {})\n",
+        env!("CARGO_PKG_VERSION"),
+        exe,
+        lsof::user_name(uid),
+        uid
+    );
+
+    let effective = Caps::effective();
+    let bounding = Caps::bounding();
+    println!("{:<20} {:<44} have", "capability", "needed for");
+    for cap in Cap::ALL {
+        println!(
+            "{:<20} {:<44} {}",
+            cap.name(),
+            cap.purpose(),
+            if effective.has(cap) { "yes" } else { "no" }
+        );
+    }
+    let missing: Vec<Cap> = Cap::ALL
+        .into_iter()
+        .filter(|c| !effective.has(*c))
+        .collect();
+    if missing.is_empty() {
+        println!("\nNothing missing: every listener can be seen, killed and disconnected.");
+        if caps::elevated_by_file() {
+            println!("\n{}", FILE_CAPS_LIMITS);
+        }
+        return Ok(());
+    }
+
+    // Granted to the file but not in effect: setcap was run, yet the
+    // kernel ignored it.
+    if let Some((on_file, raised)) = caps::file_caps(std::path::Path::new(&exe)) {
+        let ignored: Vec<_> = missing
+            .iter()
+            .filter(|c| on_file.has(**c))
+            .map(|c| c.name())
+            .collect();
+        if !ignored.is_empty() {
+            println!(
+                "\nThe binary carries {} but reaper doesn't have {}: {}.",
+                ignored.join(", "),
+                if ignored.len() == 1 { "it" } else { "them" },
+                if raised {
+                    "its filesystem may be mounted nosuid, or this session runs with no_new_privs"
+                } else {
+                    "they were set without the effective flag — use +ep"
+                }
+            );
+        }
+    }
+    let unreachable: Vec<_> = missing
+        .iter()
+        .filter(|c| !bounding.has(**c))
+        .map(|c| c.name())
+        .collect();
+    if !unreachable.is_empty() {
+        println!(
+            "\n{} outside this session's bounding set (a container?): no file capability can grant {}.",
+            unreachable.join(", "),
+            if unreachable.len() == 1 { "it" } else { "them" }
+        );
+    }
+
+    let grant: Vec<_> = Cap::ALL.into_iter().map(Cap::setcap_name).collect();
+    println!(
+        "\nInstead of running reaper as root, give the binary just these:\n\n    sudo setcap {}+ep {}\n",
+        grant.join(","),
+        exe
+    );
+    println!(
+        "Every user who can execute that file gets them, so limit who can:\n\n    sudo chgrp wheel {0} && sudo chmod 750 {0}\n",
+        exe
+    );
+    println!("{}\n", FILE_CAPS_LIMITS);
+    println!(
+        "`reaper update` replaces the binary and with it the capabilities — run setcap again afterwards.\nFor one-off use, `reaper --elevate` starts a root helper that only resolves and kills listeners."
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ages() {
+        assert_eq!(parse_age("90s"), Some(90));
+        assert_eq!(parse_age("30m"), Some(1800));
+        assert_eq!(parse_age("7d"), Some(604_800));
+        assert_eq!(parse_age("7"), None);
+        assert_eq!(parse_age("h"), None);
+    }
+}
@@ -0,0 +1,116 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::protect::Rules;
+
+/// `$XDG_<kind>_HOME/reaper`, falling back to the spec's default under
+/// `$HOME`. Under plain sudo this resolves to root's directories, which
+/// is where a root-run reaper's state belongs.
+fn xdg_dir(var: &str, fallback: &str) -> Option<PathBuf> {
+    let base = std::env::var_os(var)
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(fallback)))?;
+    Some(base.join("reaper"))
+}
+
+pub(crate) fn config_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME", ".config")
+}
+
+pub(crate) fn state_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_STATE_HOME", ".local/state")
+}
+
+/// `$XDG_RUNTIME_DIR/reaper` for sockets, or the state directory where
+/// there's no runtime dir (a root shell, a cron job).
+pub(crate) fn runtime_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .filter(|v| !v.is_empty())
+        .map(|d| PathBuf::from(d).join("reaper"))
+        .or_else(state_dir)
+}
+
+/// Settings from `$XDG_CONFIG_HOME/reaper/config.toml`. A flat subset of
+/// TOML — `key = value` lines and `#` comments — which is all reaper
+/// needs and keeps a TOML parser out of the dependency tree.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Config {
+    /// Also send each kill audit record to syslog via /dev/log.
+    pub(crate) syslog: bool,
+    /// Never kill or close anything — also forced by `--read-only`.
+    pub(crate) read_only: bool,
+    /// Protected-process rules, from their own files (see `protect`).
+    pub(crate) protected: Rules,
+}
+
+impl Config {
+    /// A missing file is the defaults; a malformed one is an error rather
+    /// than a silently ignored setting.
+    pub(crate) fn load() -> Result<Config, String> {
+        let mut config = match config_dir().map(|d| d.join("config.toml")) {
+            None => Config::default(),
+            Some(path) => match fs::read_to_string(&path) {
+                Ok(content) => parse(&content).map_err(|e| format!("{}: {}", path.display(), e))?,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Config::default(),
+                Err(e) => return Err(format!("{}: {}", path.display(), e)),
+            },
+        };
+        config.protected = Rules::load()?;
+        Ok(config)
+    }
+}
+
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        other => Err(format!("expected true or false, got {}", other)),
+    }
+}
+
+fn parse(content: &str) -> Result<Config, String> {
+    let mut config = Config::default();
+    for (n, line) in content.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(format!("line {}: expected `key = value`", n + 1));
+        };
+        let value = value.trim();
+        let at_line = |e: String| format!("line {}: {}", n + 1, e);
+        match key.trim() {
+            "syslog" => config.syslog = parse_bool(value).map_err(at_line)?,
+            "read_only" => config.read_only = parse_bool(value).map_err(at_line)?,
+            other => return Err(format!("line {}: unknown setting `{}`", n + 1, other)),
+        }
+    }
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_settings_and_comments() {
+        let config =
+            parse("# audit\nsyslog = true  # also to /dev/log\n\nread_only = false").unwrap();
+        assert!(config.syslog);
+        assert!(!config.read_only);
+        assert_eq!(parse("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_bad_values() {
+        assert!(
+            parse("sislog = true")
+                .unwrap_err()
+                .contains("unknown setting")
+        );
+        assert!(parse("syslog = yes").unwrap_err().contains("line 1"));
+        assert!(parse("syslog").is_err());
+    }
+}
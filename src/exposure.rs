@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// Who can reach a listener, judged from the address it is bound to.
+/// Ordered from least to most exposed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Exposure {
+    /// 127.0.0.0/8 or ::1 — this machine only.
+    Loopback,
+    /// 169.254.0.0/16 or fe80::/10 — the local link only.
+    LinkLocal,
+    /// RFC 1918, CGNAT (100.64.0.0/10) or IPv6 ULA (fc00::/7) — the LAN or
+    /// VPN, not the internet.
+    Private,
+    /// A routable address bound explicitly.
+    Public,
+    /// 0.0.0.0 or :: — every interface, including whatever the machine is
+    /// plugged into next. The classic accidental dev-server exposure.
+    Wildcard,
+}
+
+impl Exposure {
+    pub fn label(self) -> &'static str {
+        match self {
+            Exposure::Loopback => "loopback",
+            Exposure::LinkLocal => "link-local",
+            Exposure::Private => "private",
+            Exposure::Public => "public",
+            Exposure::Wildcard => "wildcard",
+        }
+    }
+}
+
+pub fn classify(ip: IpAddr) -> Exposure {
+    match ip {
+        IpAddr::V4(v4) => classify_v4(v4),
+        IpAddr::V6(v6) => {
+            // A v6 socket bound to ::ffff:a.b.c.d is exactly as reachable
+            // as a v4 socket bound to a.b.c.d.
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return classify_v4(v4);
+            }
+            let first = v6.segments()[0];
+            if v6.is_unspecified() {
+                Exposure::Wildcard
+            } else if v6.is_loopback() {
+                Exposure::Loopback
+            } else if first & 0xffc0 == 0xfe80 {
+                Exposure::LinkLocal
+            } else if first & 0xfe00 == 0xfc00 {
+                Exposure::Private
+            } else {
+                Exposure::Public
+            }
+        }
+    }
+}
+
+fn classify_v4(ip: Ipv4Addr) -> Exposure {
+    let [a, b, ..] = ip.octets();
+    if ip.is_unspecified() {
+        Exposure::Wildcard
+    } else if ip.is_loopback() {
+        Exposure::Loopback
+    } else if ip.is_link_local() {
+        Exposure::LinkLocal
+    } else if ip.is_private() || (a == 100 && (64..128).contains(&b)) {
+        Exposure::Private
+    } else {
+        Exposure::Public
+    }
+}
+
+/// Parse /proc/net/if_inet6: one line per IPv6 address, the address as 32
+/// hex digits in network order (unlike /proc/net/tcp6 — no per-word
+/// byte swapping), then ifindex, prefix length, scope, flags and name.
+fn parse_if_inet6(content: &str) -> Vec<(IpAddr, String)> {
+    let mut out = Vec::new();
+    for line in content.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let (Some(hex), Some(name)) = (fields.first(), fields.get(5)) else {
+            continue;
+        };
+        if hex.len() != 32 {
+            continue;
+        }
+        let Ok(bits) = u128::from_str_radix(hex, 16) else {
+            continue;
+        };
+        out.push((IpAddr::V6(Ipv6Addr::from(bits)), name.to_string()));
+    }
+    out
+}
+
+/// IPv4 addresses per interface, via getifaddrs(3) — the kernel's IPv4
+/// interface table has no /proc text equivalent that carries names.
+fn ipv4_interfaces() -> Vec<(IpAddr, String)> {
+    let mut out = Vec::new();
+    let mut head: *mut libc::ifaddrs = std::ptr::null_mut();
+    if unsafe { libc::getifaddrs(&mut head) } != 0 {
+        return out;
+    }
+    let mut cur = head;
+    while !cur.is_null() {
+        let ifa = unsafe { &*cur };
+        cur = ifa.ifa_next;
+        if ifa.ifa_addr.is_null()
+            || unsafe { (*ifa.ifa_addr).sa_family } != libc::AF_INET as libc::sa_family_t
+        {
+            continue;
+        }
+        let sin = unsafe { &*(ifa.ifa_addr as *const libc::sockaddr_in) };
+        let ip = Ipv4Addr::from(u32::from_be(sin.sin_addr.s_addr));
+        let name = unsafe { std::ffi::CStr::from_ptr(ifa.ifa_name) }
+            .to_string_lossy()
+            .into_owned();
+        out.push((IpAddr::V4(ip), name));
+    }
+    unsafe { libc::freeifaddrs(head) };
+    out
+}
+
+/// Address → owning interface name, rebuilt each scan (DHCP renewals and
+/// VPNs come and go while reaper is open; both sources are cheap).
+#[derive(Debug, Default)]
+pub struct InterfaceTable {
+    by_addr: HashMap<IpAddr, String>,
+}
+
+impl InterfaceTable {
+    pub fn load() -> InterfaceTable {
+        let mut by_addr: HashMap<IpAddr, String> = ipv4_interfaces().into_iter().collect();
+        if let Ok(content) = fs::read_to_string("/proc/net/if_inet6") {
+            by_addr.extend(parse_if_inet6(&content));
+        }
+        InterfaceTable { by_addr }
+    }
+
+    /// The interface a specific address belongs to. Wildcards belong to
+    /// all of them; an address no interface holds (a stale bind, or one
+    /// using IP_FREEBIND) belongs to none.
+    pub fn owner(&self, ip: IpAddr) -> Option<&str> {
+        let ip = match ip {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+            v4 => v4,
+        };
+        self.by_addr.get(&ip).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn classifies_v4_ranges() {
+        assert_eq!(classify(ip("0.0.0.0")), Exposure::Wildcard);
+        assert_eq!(classify(ip("127.0.0.53")), Exposure::Loopback);
+        assert_eq!(classify(ip("169.254.1.1")), Exposure::LinkLocal);
+        assert_eq!(classify(ip("10.1.2.3")), Exposure::Private);
+        assert_eq!(classify(ip("172.16.0.1")), Exposure::Private);
+        assert_eq!(classify(ip("192.168.1.10")), Exposure::Private);
+        assert_eq!(classify(ip("100.100.1.1")), Exposure::Private);
+        assert_eq!(classify(ip("172.32.0.1")), Exposure::Public);
+        assert_eq!(classify(ip("8.8.8.8")), Exposure::Public);
+    }
+
+    #[test]
+    fn classifies_v6_ranges() {
+        assert_eq!(classify(ip("::")), Exposure::Wildcard);
+        assert_eq!(classify(ip("::1")), Exposure::Loopback);
+        assert_eq!(classify(ip("fe80::1")), Exposure::LinkLocal);
+        assert_eq!(classify(ip("fd00::2")), Exposure::Private);
+        assert_eq!(classify(ip("2001:db8::1")), Exposure::Public);
+        // v4-mapped addresses are judged by their v4 half.
+        assert_eq!(classify(ip("::ffff:127.0.0.1")), Exposure::Loopback);
+        assert_eq!(classify(ip("::ffff:192.168.0.1")), Exposure::Private);
+    }
+
+    #[test]
+    fn parse_if_inet6_reads_network_order_addresses() {
+        let content = "\
+fe8000000000000000fc00fffe000001 04 40 20 80     eth0
+00000000000000000000000000000001 01 80 10 80       lo
+";
+        let parsed = parse_if_inet6(content);
+        assert_eq!(
+            parsed,
+            vec![
+                (ip("fe80::fc:ff:fe00:1"), "eth0".to_string()),
+                (ip("::1"), "lo".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn v4_mapped_addresses_belong_to_the_v4_owner() {
+        let table = InterfaceTable {
+            by_addr: [
+                (ip("127.0.0.1"), "lo".to_string()),
+                (ip("192.168.1.10"), "wlan0".to_string()),
+            ]
+            .into_iter()
+            .chain(parse_if_inet6(
+                "00000000000000000000000000000001 01 80 10 80       lo\n",
+            ))
+            .collect(),
+        };
+        assert_eq!(table.owner(ip("127.0.0.1")), Some("lo"));
+        assert_eq!(table.owner(ip("::ffff:127.0.0.1")), Some("lo"));
+        assert_eq!(table.owner(ip("::ffff:192.168.1.10")), Some("wlan0"));
+        assert_eq!(table.owner(ip("::1")), Some("lo"));
+        assert_eq!(table.owner(ip("10.0.0.1")), None);
+    }
+}
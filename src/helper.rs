@@ -0,0 +1,468 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent;
+use crate::audit::{AuditLog, KillRecord, Source};
+use crate::cli::UsageError;
+use crate::lsof::{self, KillOutcome, LsofEntry, Scanner};
+use crate::protect::{Guard, Protection, Rules};
+
+/// One request per line, JSON. The set is deliberately tiny: a root
+/// process that'll do anything an unprivileged client asks is a hole, so
+/// the helper only answers "who owns these listening sockets" and "kill
+/// this exact process incarnation, which must be holding a listener".
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub(crate) enum Request {
+    Hello,
+    Resolve {
+        inodes: Vec<u64>,
+    },
+    Kill {
+        pid: String,
+        starttime_ticks: u64,
+        /// The listener's port, confirmed by a person: required to kill
+        /// a process the system rules protect.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        confirm_port: Option<u16>,
+    },
+}
+
+/// One response line per request. Exactly one of the payload fields is
+/// set, or `error`.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Response {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<String>,
+    /// Hello: the helper's version, and whether it really runs as root.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) root: Option<bool>,
+    /// Resolve: listening-socket inode → pid.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) owners: Option<HashMap<u64, String>>,
+    /// Kill: how it went.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) outcome: Option<KillOutcome>,
+}
+
+fn refuse(message: String) -> Response {
+    Response {
+        error: Some(message),
+        ..Default::default()
+    }
+}
+
+/// What the helper holds every kill to — pid 1, its own ancestry and
+/// /etc/reaper/protected, never the asking user's rules — and where it
+/// keeps its own record of them.
+struct Policy {
+    guard: Guard,
+    audit: AuditLog,
+}
+
+impl Policy {
+    fn load() -> Result<Policy, String> {
+        Ok(Policy {
+            guard: Guard::new(Rules::system()?),
+            audit: AuditLog::system(),
+        })
+    }
+}
+
+/// Answer one request from the user with uid `client`.
+fn handle(request: Request, policy: &Policy, client: u32) -> Response {
+    match request {
+        Request::Hello => Response {
+            version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            root: Some(unsafe { libc::geteuid() } == 0),
+            ..Default::default()
+        },
+        Request::Resolve { inodes } => {
+            // Only listening sockets — not a general "whose fd is this".
+            let listening = lsof::listening_inodes();
+            let wanted = inodes
+                .into_iter()
+                .filter(|i| listening.contains(i))
+                .collect();
+            Response {
+                owners: Some(lsof::build_inode_to_pid(&wanted)),
+                ..Default::default()
+            }
+        }
+        Request::Kill {
+            pid,
+            starttime_ticks,
+            confirm_port,
+        } => {
+            if pid == "1" {
+                return refuse("refusing to signal pid 1".to_string());
+            }
+            // The helper's own scan, not the client's word, says what the
+            // process is and which port it holds.
+            let Some(target) = Scanner::default()
+                .scan()
+                .into_iter()
+                .find(|p| p.pid == pid && p.starttime_ticks == Some(starttime_ticks))
+            else {
+                return refuse(format!(
+                    "pid {} (starttime {}) holds no listening socket; the helper only kills listeners",
+                    pid, starttime_ticks
+                ));
+            };
+            match policy.guard.check(&target) {
+                Some(Protection::Pinned(why)) => return refuse(format!("won't kill: {}", why)),
+                Some(rule) if confirm_port != Some(target.port) => {
+                    return refuse(format!(
+                        "{}; send confirm_port {} to kill it",
+                        rule.reason(),
+                        target.port
+                    ));
+                }
+                _ => {}
+            }
+            let outcome = lsof::kill_process_verified(&target.pid, target.starttime_ticks);
+            let mut record = KillRecord::new(Source::Helper, &target, &outcome);
+            record.user = lsof::user_name(client);
+            record.sudo_user = None;
+            if let Err(e) = policy.audit.append(&record) {
+                eprintln!("reaper helper: kill not audited: {}", e);
+            }
+            match outcome {
+                Ok(outcome) => Response {
+                    outcome: Some(outcome),
+                    ..Default::default()
+                },
+                Err(e) => refuse(e.to_string()),
+            }
+        }
+    }
+}
+
+/// Answer requests until the client hangs up.
+fn serve(
+    input: impl BufRead,
+    mut output: impl Write,
+    policy: &Policy,
+    client: u32,
+) -> io::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str(&line) {
+            Ok(request) => handle(request, policy, client),
+            Err(e) => refuse(format!("bad request: {}", e)),
+        };
+        let mut out = serde_json::to_string(&response).map_err(io::Error::other)?;
+        out.push('\n');
+        output.write_all(out.as_bytes())?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+/// `reaper helper [--listen PATH [--group NAME]]`: serve on stdin/stdout
+/// (how the TUI runs it through sudo or pkexec), or on a Unix socket as
+/// a long-running root service. The socket is mode 0660, so `--group`
+/// decides who may use it.
+pub(crate) fn run(args: &[String]) -> Result<(), UsageError> {
+    let mut listen = None;
+    let mut group = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .cloned()
+                .ok_or_else(|| UsageError(format!("{} needs a value", arg)))
+        };
+        match arg.as_str() {
+            "--listen" => listen = Some(value()?),
+            "--group" => group = Some(value()?),
+            other => return Err(UsageError(format!("unknown helper option: {}", other))),
+        }
+    }
+    if unsafe { libc::geteuid() } != 0 {
+        eprintln!("reaper helper has to run as root (sudo reaper helper)");
+        std::process::exit(1);
+    }
+    let policy = Policy::load().unwrap_or_else(|e| {
+        eprintln!("reaper helper: {}", e);
+        std::process::exit(1);
+    });
+    let result = match listen {
+        None => serve(
+            io::stdin().lock(),
+            io::stdout().lock(),
+            &policy,
+            invoking_uid(),
+        ),
+        Some(path) => listen_on(&path, group.as_deref(), policy),
+    };
+    if let Err(e) = result {
+        eprintln!("reaper helper: {}", e);
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Who a helper on stdin/stdout works for: the user sudo or pkexec ran
+/// it on behalf of.
+fn invoking_uid() -> u32 {
+    ["SUDO_UID", "PKEXEC_UID"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok()?.parse().ok())
+        .unwrap_or_else(|| unsafe { libc::getuid() })
+}
+
+fn listen_on(path: &str, group: Option<&str>, policy: Policy) -> io::Result<()> {
+    let gid = match group {
+        Some(name) => Some(
+            lsof::group_id(name)
+                .ok_or_else(|| io::Error::other(format!("no such group: {}", name)))?,
+        ),
+        None => None,
+    };
+    // A stale socket from a previous run would make bind fail.
+    let _ = fs::remove_file(path);
+    let listener = UnixListener::bind(path)?;
+    if let Some(gid) = gid {
+        std::os::unix::fs::chown(path, None, Some(gid))?;
+    }
+    fs::set_permissions(path, fs::Permissions::from_mode(0o660))?;
+    let policy = Arc::new(policy);
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let policy = Arc::clone(&policy);
+        std::thread::spawn(move || {
+            let Ok(reader) = stream.try_clone() else {
+                return;
+            };
+            let client = agent::peer_uid(&stream).unwrap_or(u32::MAX);
+            let _ = serve(BufReader::new(reader), stream, &policy, client);
+        });
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+enum Link {
+    Pipe {
+        // Kept so the child handle lives as long as the pipes do.
+        _child: Child,
+        stdin: ChildStdin,
+        stdout: BufReader<ChildStdout>,
+    },
+    Socket(BufReader<UnixStream>),
+}
+
+/// The TUI's end of a privileged helper.
+#[derive(Debug)]
+pub struct Helper {
+    link: Link,
+}
+
+/// Transport failures all mean the same thing to the caller: the helper
+/// is gone and won't be back.
+fn gone(e: io::Error) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::BrokenPipe,
+        format!("privileged helper is gone: {}", e),
+    )
+}
+
+impl Helper {
+    /// Start `reaper helper` through `via` (`sudo` or `pkexec`), which
+    /// prompts for a password on the terminal — so this has to happen
+    /// before the TUI takes the screen.
+    pub(crate) fn spawn(via: &str) -> io::Result<Helper> {
+        let exe = std::env::current_exe()?;
+        let mut cmd = Command::new(via);
+        if via == "sudo" {
+            cmd.arg("--");
+        }
+        let mut child = cmd
+            .arg(exe)
+            .arg("helper")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(io::Error::other("helper pipes unavailable"));
+        };
+        Helper {
+            link: Link::Pipe {
+                _child: child,
+                stdin,
+                stdout: BufReader::new(stdout),
+            },
+        }
+        .greet()
+    }
+
+    /// Use a helper already running as a service (`reaper helper --listen`).
+    pub(crate) fn connect(path: &str) -> io::Result<Helper> {
+        let stream = UnixStream::connect(path)?;
+        Helper {
+            link: Link::Socket(BufReader::new(stream)),
+        }
+        .greet()
+    }
+
+    fn greet(mut self) -> io::Result<Helper> {
+        let hello = self.request(&Request::Hello)?;
+        if hello.version.as_deref() != Some(env!("CARGO_PKG_VERSION")) {
+            return Err(io::Error::other(format!(
+                "helper is version {}, this is {}",
+                hello.version.as_deref().unwrap_or("?"),
+                env!("CARGO_PKG_VERSION")
+            )));
+        }
+        if hello.root != Some(true) {
+            return Err(io::Error::other("helper isn't running as root"));
+        }
+        Ok(self)
+    }
+
+    fn request(&mut self, request: &Request) -> io::Result<Response> {
+        let mut line = serde_json::to_string(request).map_err(io::Error::other)?;
+        line.push('\n');
+        let mut reply = String::new();
+        match &mut self.link {
+            Link::Pipe { stdin, stdout, .. } => {
+                stdin.write_all(line.as_bytes()).map_err(gone)?;
+                stdin.flush().map_err(gone)?;
+                stdout.read_line(&mut reply).map_err(gone)?;
+            }
+            Link::Socket(stream) => {
+                stream.get_mut().write_all(line.as_bytes()).map_err(gone)?;
+                stream.read_line(&mut reply).map_err(gone)?;
+            }
+        }
+        if reply.is_empty() {
+            return Err(gone(io::ErrorKind::UnexpectedEof.into()));
+        }
+        let response: Response = serde_json::from_str(&reply).map_err(io::Error::other)?;
+        match response.error {
+            Some(e) => Err(io::Error::other(e)),
+            None => Ok(response),
+        }
+    }
+
+    pub(crate) fn resolve(&mut self, inodes: &[u64]) -> io::Result<HashMap<u64, String>> {
+        let response = self.request(&Request::Resolve {
+            inodes: inodes.to_vec(),
+        })?;
+        Ok(response.owners.unwrap_or_default())
+    }
+
+    /// The TUI has already had the kill confirmed — with the port typed
+    /// out, if its rules protect the process — so it vouches for the port.
+    pub(crate) fn kill(&mut self, p: &LsofEntry, starttime_ticks: u64) -> io::Result<KillOutcome> {
+        let response = self.request(&Request::Kill {
+            pid: p.pid.clone(),
+            starttime_ticks,
+            confirm_port: Some(p.port),
+        })?;
+        response
+            .outcome
+            .ok_or_else(|| io::Error::other("helper sent no outcome"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exchange(input: &str) -> Vec<Response> {
+        let policy = Policy {
+            guard: Guard::new(Rules::default()),
+            audit: AuditLog::default(),
+        };
+        let mut output = Vec::new();
+        serve(input.as_bytes(), &mut output, &policy, 0).unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn requests_are_tagged_json() {
+        assert_eq!(
+            serde_json::to_string(&Request::Kill {
+                pid: "42".into(),
+                starttime_ticks: 7,
+                confirm_port: None
+            })
+            .unwrap(),
+            r#"{"op":"kill","pid":"42","starttime_ticks":7}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<Request>(r#"{"op":"resolve","inodes":[1,2]}"#).unwrap(),
+            Request::Resolve { inodes: vec![1, 2] }
+        );
+    }
+
+    #[test]
+    fn answers_each_line_and_survives_garbage() {
+        let me = std::process::id();
+        let responses = exchange(&format!(
+            "{{\"op\":\"hello\"}}\n\nnot json\n{{\"op\":\"kill\",\"pid\":\"1\",\"starttime_ticks\":1}}\n{{\"op\":\"kill\",\"pid\":\"{}\",\"starttime_ticks\":1}}\n",
+            me
+        ));
+        assert_eq!(responses.len(), 4);
+        assert_eq!(
+            responses[0].version.as_deref(),
+            Some(env!("CARGO_PKG_VERSION"))
+        );
+        assert!(responses[1].error.as_ref().unwrap().contains("bad request"));
+        assert!(responses[2].error.as_ref().unwrap().contains("pid 1"));
+        // The test process listens on nothing: not the helper's business.
+        assert!(
+            responses[3]
+                .error
+                .as_ref()
+                .unwrap()
+                .contains("no listening socket")
+        );
+    }
+
+    #[test]
+    fn never_kills_its_own_ancestry() {
+        // The test process listening makes it a target — one the helper
+        // sees as its own, and so pinned whatever the client confirms.
+        let _listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let me = std::process::id().to_string();
+        let ticks = lsof::starttime_ticks(&me).unwrap();
+        let responses = exchange(&format!(
+            "{{\"op\":\"kill\",\"pid\":\"{}\",\"starttime_ticks\":{},\"confirm_port\":1}}\n",
+            me, ticks
+        ));
+        assert!(
+            responses[0]
+                .error
+                .as_ref()
+                .unwrap()
+                .starts_with("won't kill"),
+            "{:?}",
+            responses[0]
+        );
+    }
+
+    #[test]
+    fn resolve_ignores_non_listening_inodes() {
+        let responses = exchange("{\"op\":\"resolve\",\"inodes\":[0]}\n");
+        assert_eq!(responses[0].owners, Some(HashMap::new()));
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::lsof::{LsofEntry, MemoryMetric};
+
+/// Samples kept per process — at the 1s refresh, the last half minute.
+/// Enough to tell a leak from a plateau without the sparkline outgrowing
+/// the detail panel.
+pub(crate) const SAMPLES: usize = 30;
+
+/// One process's recent resource readings, oldest first.
+#[derive(Debug, Default)]
+pub(crate) struct Trend {
+    pub(crate) memory_mb: VecDeque<f64>,
+    pub(crate) cpu_percent: VecDeque<f64>,
+}
+
+fn push_bounded(buf: &mut VecDeque<f64>, value: f64) {
+    if buf.len() == SAMPLES {
+        buf.pop_front();
+    }
+    buf.push_back(value);
+}
+
+/// Per-process ring buffers of memory and CPU across refreshes, keyed by
+/// pid *and* starttime so a recycled PID starts a fresh trend instead of
+/// continuing a dead process's line.
+#[derive(Debug, Default)]
+pub(crate) struct ResourceHistory {
+    trends: HashMap<(String, Option<u64>), Trend>,
+}
+
+impl ResourceHistory {
+    /// Memory samples are taken in `metric`; switching metrics should
+    /// `clear()` rather than splice two different measures into one line.
+    pub(crate) fn record(&mut self, scan: &[LsofEntry], metric: MemoryMetric) {
+        let mut next: HashMap<(String, Option<u64>), Trend> = HashMap::new();
+        for p in scan.iter().filter(|p| p.is_killable()) {
+            let key = (p.pid.clone(), p.starttime_ticks);
+            // One sample per process per scan, even when it backs several
+            // listeners (v4 + v6, multiple ports).
+            if next.contains_key(&key) {
+                continue;
+            }
+            let mut trend = self.trends.remove(&key).unwrap_or_default();
+            push_bounded(&mut trend.memory_mb, p.memory(metric));
+            if let Some(cpu) = p.cpu_percent {
+                push_bounded(&mut trend.cpu_percent, cpu);
+            }
+            next.insert(key, trend);
+        }
+        // Whatever wasn't carried over belongs to processes that no longer
+        // hold a listener.
+        self.trends = next;
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.trends.clear();
+    }
+
+    pub(crate) fn get(&self, p: &LsofEntry) -> Option<&Trend> {
+        self.trends.get(&(p.pid.clone(), p.starttime_ticks))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(pid: &str, port: u16, memory_mb: f64) -> LsofEntry {
+        LsofEntry {
+            memory_mb,
+            cpu_percent: Some(1.0),
+            ..LsofEntry::test(pid, port, port.into())
+        }
+    }
+
+    #[test]
+    fn records_one_sample_per_process_and_drops_dead_ones() {
+        let mut history = ResourceHistory::default();
+        history.record(
+            &[entry("10", 80, 1.0), entry("10", 443, 1.0)],
+            MemoryMetric::Rss,
+        );
+        history.record(
+            &[entry("10", 80, 2.0), entry("20", 22, 5.0)],
+            MemoryMetric::Rss,
+        );
+        let trend = history.get(&entry("10", 80, 0.0)).unwrap();
+        assert_eq!(trend.memory_mb, [1.0, 2.0]);
+        assert_eq!(trend.cpu_percent.len(), 2);
+
+        history.record(&[entry("20", 22, 6.0)], MemoryMetric::Rss);
+        assert!(history.get(&entry("10", 80, 0.0)).is_none());
+        assert_eq!(
+            history.get(&entry("20", 22, 0.0)).unwrap().memory_mb,
+            [5.0, 6.0]
+        );
+    }
+
+    #[test]
+    fn keeps_only_the_newest_samples() {
+        let mut history = ResourceHistory::default();
+        for i in 0..SAMPLES + 5 {
+            history.record(&[entry("10", 80, i as f64)], MemoryMetric::Rss);
+        }
+        let trend = history.get(&entry("10", 80, 0.0)).unwrap();
+        assert_eq!(trend.memory_mb.len(), SAMPLES);
+        assert_eq!(trend.memory_mb.front(), Some(&5.0));
+    }
+}
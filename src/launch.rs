@@ -0,0 +1,392 @@
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::process::CommandExt;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use crate::caps;
+use crate::config;
+use crate::lsof::{self, LsofEntry};
+
+/// Everything needed to start a listener again the way it was started:
+/// the raw argv (not the prettified `parse_cmdline` string — that one
+/// basenames paths and loses quoting), working directory, environment
+/// and the identity it ran as.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Launch {
+    pub(crate) argv: Vec<OsString>,
+    /// The binary that was running, from /proc/<pid>/exe: what gets
+    /// executed, so neither a relative argv[0] nor one the process
+    /// rewrote decides it.
+    pub(crate) exe: PathBuf,
+    pub(crate) cwd: PathBuf,
+    pub(crate) env: Vec<(OsString, OsString)>,
+    pub(crate) uid: u32,
+    pub(crate) gid: u32,
+    pub(crate) groups: Vec<u32>,
+    /// The port it was listening on, for the log and script names.
+    pub(crate) port: u16,
+}
+
+/// `/proc/<pid>/cmdline` and `environ` are NUL-terminated lists.
+fn split_nul(raw: &[u8]) -> Vec<OsString> {
+    raw.split(|&b| b == 0)
+        .filter(|s| !s.is_empty())
+        .map(|s| OsString::from_vec(s.to_vec()))
+        .collect()
+}
+
+fn parse_env(raw: &[u8]) -> Vec<(OsString, OsString)> {
+    split_nul(raw)
+        .into_iter()
+        .filter_map(|kv| {
+            let bytes = kv.into_vec();
+            // `=` at 0 would be an empty name; skip such oddities.
+            let eq = bytes.iter().position(|&b| b == b'=').filter(|&i| i > 0)?;
+            Some((
+                OsString::from_vec(bytes[..eq].to_vec()),
+                OsString::from_vec(bytes[eq + 1..].to_vec()),
+            ))
+        })
+        .collect()
+}
+
+/// (real uid, real gid, supplementary groups) from /proc/<pid>/status.
+fn parse_ids(status: &str) -> Option<(u32, u32, Vec<u32>)> {
+    let field = |key: &str| status.lines().find_map(|l| l.strip_prefix(key));
+    let first = |key: &str| field(key)?.split_whitespace().next()?.parse().ok();
+    let groups = field("Groups:")
+        .unwrap_or_default()
+        .split_whitespace()
+        .filter_map(|g| g.parse().ok())
+        .collect();
+    Some((first("Uid:")?, first("Gid:")?, groups))
+}
+
+/// POSIX single-quoting: safe for any bytes but NUL, which argv and
+/// environ can't contain anyway.
+fn shell_quote(s: &OsStr) -> Vec<u8> {
+    let bytes = s.as_bytes();
+    if !bytes.is_empty()
+        && bytes
+            .iter()
+            .all(|b| b.is_ascii_alphanumeric() || b"-_./:=@%+,".contains(b))
+    {
+        return bytes.to_vec();
+    }
+    let mut out = vec![b'\''];
+    for &b in bytes {
+        if b == b'\'' {
+            out.extend_from_slice(b"'\\''");
+        } else {
+            out.push(b);
+        }
+    }
+    out.push(b'\'');
+    out
+}
+
+/// /proc/<pid>/exe of a binary replaced since it started (a rebuild, a
+/// package upgrade) reads `<path> (deleted)`; the path now holds the
+/// replacement, which is what a restart should run.
+fn exe_path(link: PathBuf) -> PathBuf {
+    match link.as_os_str().as_bytes().strip_suffix(b" (deleted)") {
+        Some(path) => PathBuf::from(OsStr::from_bytes(path)),
+        None => link,
+    }
+}
+
+/// `node-3000`: readable and unique enough for per-port file names.
+fn file_stem(argv0: &OsStr, port: u16) -> String {
+    let name: String = PathBuf::from(argv0)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("{}-{}", name, port)
+}
+
+impl Launch {
+    /// Read the launch context of the process behind `p`. Fails when any
+    /// of it is unreadable — environ and cwd of other users' processes
+    /// need root — or when the PID turned out to be someone else by the
+    /// time we'd read it. With file capabilities, reaper could read any
+    /// user's environment on its own user's behalf, so it refuses to.
+    pub(crate) fn capture(p: &LsofEntry) -> io::Result<Launch> {
+        let gone = || io::Error::other("process has exited or its PID was recycled");
+        if !p.is_killable() || lsof::process_state(&p.pid, p.starttime_ticks).is_none() {
+            return Err(gone());
+        }
+        let proc = |file: &str| format!("/proc/{}/{}", p.pid, file);
+        let (uid, gid, groups) = parse_ids(&fs::read_to_string(proc("status"))?)
+            .ok_or_else(|| io::Error::other("unreadable /proc status"))?;
+        if uid != unsafe { libc::getuid() } && caps::elevated_by_file() {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!(
+                    "it runs as {}, and reaper's file capabilities don't extend to reading other users' environments",
+                    lsof::user_name(uid)
+                ),
+            ));
+        }
+        let argv = split_nul(&fs::read(proc("cmdline"))?);
+        let exe = exe_path(fs::read_link(proc("exe"))?);
+        let env = parse_env(&fs::read(proc("environ"))?);
+        let cwd = fs::read_link(proc("cwd"))?;
+        // Everything above could belong to a new process that got the
+        // PID mid-read; same starttime afterwards rules that out.
+        if lsof::process_state(&p.pid, p.starttime_ticks).is_none() {
+            return Err(gone());
+        }
+        if argv.is_empty() {
+            return Err(io::Error::other("no command line (kernel thread?)"));
+        }
+        Ok(Launch {
+            argv,
+            exe,
+            cwd,
+            env,
+            uid,
+            gid,
+            groups,
+            port: p.port,
+        })
+    }
+
+    /// Start it again, detached: its own session (so the terminal's
+    /// Ctrl+C and hangup don't reach it), stdin from /dev/null and output
+    /// appended to `log`. Under sudo it drops back to the original user —
+    /// a dev server restarted as root would be worse than none.
+    pub(crate) fn spawn(&self, log: &PathBuf) -> io::Result<u32> {
+        let own_uid = unsafe { libc::geteuid() };
+        let drop_to = match (own_uid, self.uid) {
+            (a, b) if a == b => None,
+            (0, _) => Some((self.uid, self.gid, self.groups.clone())),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    format!(
+                        "it ran as {}; only root can start it as that user",
+                        lsof::user_name(self.uid)
+                    ),
+                ));
+            }
+        };
+        if let Some(dir) = log.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let out = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .mode(0o600)
+            .open(log)?;
+        let mut cmd = Command::new(&self.exe);
+        cmd.arg0(&self.argv[0])
+            .args(&self.argv[1..])
+            .current_dir(&self.cwd)
+            .env_clear()
+            .envs(self.env.iter().map(|(k, v)| (k, v)))
+            .stdin(Stdio::null())
+            .stdout(out.try_clone()?)
+            .stderr(out);
+        unsafe {
+            cmd.pre_exec(move || {
+                if libc::setsid() < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                // Groups, then gid, then uid: each step needs the
+                // privilege the next one gives up.
+                if let Some((uid, gid, groups)) = &drop_to
+                    && (libc::setgroups(groups.len(), groups.as_ptr()) < 0
+                        || libc::setgid(*gid) < 0
+                        || libc::setuid(*uid) < 0)
+                {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        let mut child = cmd.spawn()?;
+        let pid = child.id();
+        // Reap it if it exits while we're still running, so it can't
+        // linger as our zombie; once reaper quits, init adopts it.
+        std::thread::spawn(move || child.wait());
+        Ok(pid)
+    }
+
+    /// Where a restarted process's output goes.
+    pub(crate) fn log_path(&self) -> Option<PathBuf> {
+        config::state_dir().map(|d| {
+            d.join("logs")
+                .join(format!("{}.log", file_stem(&self.argv[0], self.port)))
+        })
+    }
+
+    /// A standalone `sh` script that starts the same process in the same
+    /// directory with exactly the same environment.
+    pub(crate) fn to_script(&self) -> Vec<u8> {
+        let mut s = b"#!/bin/sh\n".to_vec();
+        s.extend_from_slice(
+            format!(
+                "# Launch of the listener on :{} as {}, captured by reaper.\n\
+                 # The environment below is verbatim and may contain secrets.\n",
+                self.port,
+                lsof::user_name(self.uid)
+            )
+            .as_bytes(),
+        );
+        s.extend_from_slice(b"cd ");
+        s.extend(shell_quote(self.cwd.as_os_str()));
+        s.extend_from_slice(b" || exit 1\nexec env -i");
+        for (k, v) in &self.env {
+            let mut kv = k.clone();
+            kv.push("=");
+            kv.push(v);
+            s.extend_from_slice(b" \\\n  ");
+            s.extend(shell_quote(&kv));
+        }
+        s.extend_from_slice(b" \\\n  ");
+        // sh has no way to pass argv[0]; the binary's path stands in.
+        s.extend(shell_quote(self.exe.as_os_str()));
+        for arg in &self.argv[1..] {
+            s.push(b' ');
+            s.extend(shell_quote(arg));
+        }
+        s.push(b'\n');
+        s
+    }
+
+    /// Write `to_script()` under the state directory, owner-only since it
+    /// carries the whole environment. Returns the path.
+    pub(crate) fn export(&self) -> io::Result<PathBuf> {
+        let dir = config::state_dir()
+            .ok_or_else(|| io::Error::other("neither XDG_STATE_HOME nor HOME is set"))?
+            .join("launches");
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("{}.sh", file_stem(&self.argv[0], self.port)));
+        // Replace rather than truncate in place, so an existing file's
+        // looser mode can't carry over.
+        let _ = fs::remove_file(&path);
+        let mut file: File = OpenOptions::new()
+            .create_new(true)
+            .write(true)
+            .mode(0o700)
+            .open(&path)?;
+        file.write_all(&self.to_script())?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn os(s: &str) -> OsString {
+        s.into()
+    }
+
+    #[test]
+    fn parses_raw_argv_and_environ() {
+        assert_eq!(
+            split_nul(b"node\0--port\0\0a b\0"),
+            vec![os("node"), os("--port"), os("a b")]
+        );
+        assert_eq!(
+            parse_env(b"A=1\0B=x=y\0=odd\0EMPTY=\0"),
+            vec![
+                (os("A"), os("1")),
+                (os("B"), os("x=y")),
+                (os("EMPTY"), os(""))
+            ]
+        );
+    }
+
+    #[test]
+    fn a_replaced_binary_restarts_as_its_replacement() {
+        assert_eq!(
+            exe_path("/usr/bin/node (deleted)".into()),
+            PathBuf::from("/usr/bin/node")
+        );
+        assert_eq!(
+            exe_path("/usr/bin/node".into()),
+            PathBuf::from("/usr/bin/node")
+        );
+    }
+
+    #[test]
+    fn parses_ids_from_status() {
+        let status = "Name:\tnode\nUid:\t1000\t1000\t1000\t1000\nGid:\t100\t100\t100\t100\nGroups:\t10 100 \n";
+        assert_eq!(parse_ids(status), Some((1000, 100, vec![10, 100])));
+    }
+
+    #[test]
+    fn script_quotes_everything_that_needs_it() {
+        let launch = Launch {
+            argv: vec![
+                os("/usr/bin/python3"),
+                os("-m"),
+                os("http.server"),
+                os("it's"),
+            ],
+            exe: "/usr/bin/python3.12".into(),
+            cwd: "/srv/my app".into(),
+            env: vec![
+                (os("PATH"), os("/usr/bin")),
+                (os("GREETING"), os("hi there")),
+            ],
+            uid: 0,
+            gid: 0,
+            groups: Vec::new(),
+            port: 8000,
+        };
+        let script = String::from_utf8(launch.to_script()).unwrap();
+        assert!(script.starts_with("#!/bin/sh\n"));
+        assert!(script.contains("cd '/srv/my app' || exit 1\n"));
+        assert!(script.contains("  PATH=/usr/bin \\\n  'GREETING=hi there' \\\n"));
+        assert!(script.ends_with("\n  /usr/bin/python3.12 -m http.server 'it'\\''s'\n"));
+        assert_eq!(file_stem(&launch.argv[0], 8000), "python3-8000");
+    }
+
+    #[test]
+    fn captures_a_live_process() {
+        let mut child = Command::new("sleep")
+            .arg("30")
+            .current_dir("/tmp")
+            .env_clear()
+            .env("REAPER_TEST", "a b")
+            .spawn()
+            .unwrap();
+        let pid = child.id().to_string();
+        let ticks = fs::read_to_string(format!("/proc/{}/stat", pid))
+            .ok()
+            .and_then(|s| {
+                let after = s.rsplit_once(')')?.1;
+                after.split_whitespace().nth(19)?.parse().ok()
+            });
+        let entry = LsofEntry {
+            command: "sleep 30".into(),
+            starttime_ticks: ticks,
+            ..LsofEntry::test(&pid, 1234, 1)
+        };
+        let launch = Launch::capture(&entry).unwrap();
+        assert_eq!(launch.argv, vec![os("sleep"), os("30")]);
+        assert!(launch.exe.is_absolute());
+        assert_eq!(launch.cwd, PathBuf::from("/tmp"));
+        assert_eq!(launch.env, vec![(os("REAPER_TEST"), os("a b"))]);
+        assert_eq!(launch.uid, unsafe { libc::getuid() });
+
+        // A different starttime is a different process.
+        let wrong = LsofEntry {
+            starttime_ticks: ticks.map(|t| t + 1),
+            ..entry
+        };
+        assert!(Launch::capture(&wrong).is_err());
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+}
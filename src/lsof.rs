@@ -1,19 +1,46 @@
 use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::io;
-use std::net::{Ipv4Addr, Ipv6Addr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::sync::OnceLock;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 
-#[derive(Debug, Clone)]
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::agent;
+use crate::exposure::{self, Exposure, InterfaceTable};
+use crate::helper::Helper;
+use crate::sock_diag;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LsofEntry {
     pub command: String,
     pub pid: String,
     pub user: String,
     pub local_addr: String,
+    /// The bound address itself; `local_addr` is its display form.
+    pub ip: IpAddr,
+    /// Interface that owns `ip`, when it's a specific address one does.
+    pub interface: Option<String>,
     pub port: u16,
-    pub protocol: &'static str,
+    #[serde(deserialize_with = "known_protocol")]
+    pub protocol: Protocol,
+    /// Resident set size (VmRSS). Always available for readable PIDs, but
+    /// counts shared pages in full — pre-fork servers look N times bigger
+    /// than they are.
     pub memory_mb: f64,
+    /// VmSwap from /proc/<pid>/status, readable wherever VmRSS is — the
+    /// swap figure when smaps_rollup isn't.
+    #[serde(default)]
+    pub swap_mb: f64,
+    /// PSS/USS/swap from /proc/<pid>/smaps_rollup. `None` when the file is
+    /// unreadable (other users' processes without sudo, kernels < 4.14).
+    pub smaps: Option<SmapsRollup>,
+    /// CPU usage over the interval since the previous scan, as a percent
+    /// of one core (so a busy multi-threaded server can exceed 100).
+    /// `None` on a process's first scan — there is no interval yet — and
+    /// for restricted rows.
+    pub cpu_percent: Option<f64>,
     pub start_time: Option<SystemTime>,
     /// Raw starttime from /proc/<pid>/stat (clock ticks since boot).
     /// Identifies a specific incarnation of a PID: if the kernel recycles
@@ -23,6 +50,132 @@ pub struct LsofEntry {
     /// Working directory the process was started in, when readable.
     /// `None` for restricted PIDs (other users) or kernel threads.
     pub cwd: Option<String>,
+    /// Socket inode from /proc/net/tcp{,6} — the listening socket itself,
+    /// stable for as long as it stays open.
+    pub inode: u64,
+    /// Clients connected to this listener right now.
+    pub connections: Vec<Connection>,
+    /// Accept queue fill, from the listener's tx_queue:rx_queue token.
+    pub backlog: Backlog,
+    /// Restricted rows only: processes of the socket's user that look
+    /// like its owner, best first. Guesses — see `Candidate`.
+    pub probable: Vec<Candidate>,
+}
+
+/// A guess at the process behind a restricted row. Its cmdline and stat
+/// are world-readable even when its fds aren't — but the fd is the only
+/// proof of ownership, so a candidate is shown and never killed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Candidate {
+    pub pid: String,
+    pub starttime_ticks: Option<u64>,
+    pub command: String,
+    /// The strongest reason it's a candidate.
+    pub hint: String,
+}
+
+/// `TCP`, `TCP6` or `TCP4/6`. An alias rather than a bare `&'static str`
+/// in `LsofEntry`, because serde would otherwise try to borrow the field
+/// from its input instead of calling `known_protocol`.
+pub type Protocol = &'static str;
+
+/// `protocol` is one of a handful of fixed labels; deserializing maps the
+/// text back onto them.
+fn known_protocol<'de, D: Deserializer<'de>>(d: D) -> Result<&'static str, D::Error> {
+    let name = String::deserialize(d)?;
+    ["TCP", "TCP6", "TCP4/6"]
+        .into_iter()
+        .find(|p| *p == name)
+        .ok_or_else(|| serde::de::Error::custom(format!("unknown protocol {}", name)))
+}
+
+/// Identity of one table row across scans: the listening socket plus the
+/// process incarnation that owns it. Row positions shift every refresh
+/// (re-sorts, listeners coming and going); this doesn't.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EntryId {
+    pid: String,
+    starttime_ticks: Option<u64>,
+    port: u16,
+    inode: u64,
+}
+
+/// The figures from /proc/<pid>/smaps_rollup that RSS alone can't give.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SmapsRollup {
+    /// Proportional set size: each shared page split across its sharers.
+    pub pss_mb: f64,
+    /// Unique set size: private pages only — what killing it would free.
+    pub uss_mb: f64,
+    pub swap_mb: f64,
+}
+
+/// Which figure drives the MEM column and memory sort.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MemoryMetric {
+    Rss,
+    Pss,
+    Uss,
+    Swap,
+}
+
+impl MemoryMetric {
+    pub fn label(self) -> &'static str {
+        match self {
+            MemoryMetric::Rss => "RSS",
+            MemoryMetric::Pss => "PSS",
+            MemoryMetric::Uss => "USS",
+            MemoryMetric::Swap => "SWAP",
+        }
+    }
+
+    pub fn next(self) -> MemoryMetric {
+        match self {
+            MemoryMetric::Rss => MemoryMetric::Pss,
+            MemoryMetric::Pss => MemoryMetric::Uss,
+            MemoryMetric::Uss => MemoryMetric::Swap,
+            MemoryMetric::Swap => MemoryMetric::Rss,
+        }
+    }
+}
+
+pub fn format_mb(mb: f64) -> String {
+    if mb < 1.0 {
+        format!("{:.1}KB", mb * 1024.0)
+    } else if mb >= 1024.0 {
+        format!("{:.1}GB", mb / 1024.0)
+    } else {
+        format!("{:.1}MB", mb)
+    }
+}
+
+#[cfg(test)]
+impl LsofEntry {
+    /// A readable TCP listener on every address, for tests to adjust
+    /// with struct-update syntax.
+    pub(crate) fn test(pid: &str, port: u16, inode: u64) -> LsofEntry {
+        LsofEntry {
+            command: "server".into(),
+            pid: pid.into(),
+            user: "u".into(),
+            local_addr: "*".into(),
+            ip: Ipv4Addr::UNSPECIFIED.into(),
+            interface: None,
+            port,
+            protocol: "TCP",
+            memory_mb: 0.0,
+            swap_mb: 0.0,
+            smaps: None,
+            cpu_percent: None,
+            start_time: None,
+            starttime_ticks: Some(1),
+            cwd: None,
+            inode,
+            connections: Vec::new(),
+            backlog: Default::default(),
+            probable: Vec::new(),
+        }
+    }
 }
 
 impl LsofEntry {
@@ -36,13 +189,37 @@ impl LsofEntry {
         }
     }
 
-    pub fn get_memory_display(&self) -> String {
-        if self.memory_mb < 1.0 {
-            format!("{:.1}KB", self.memory_mb * 1024.0)
-        } else if self.memory_mb >= 1024.0 {
-            format!("{:.1}GB", self.memory_mb / 1024.0)
+    /// The chosen memory figure. Swap falls back to VmSwap; PSS and USS
+    /// fall back to RSS when smaps_rollup couldn't be read — a real if
+    /// inflated number beats a blank, and `memory_is_rss()` says so.
+    pub fn memory(&self, metric: MemoryMetric) -> f64 {
+        match (metric, self.smaps) {
+            (MemoryMetric::Swap, s) => s.map_or(self.swap_mb, |s| s.swap_mb),
+            (MemoryMetric::Rss, _) | (_, None) => self.memory_mb,
+            (MemoryMetric::Pss, Some(s)) => s.pss_mb,
+            (MemoryMetric::Uss, Some(s)) => s.uss_mb,
+        }
+    }
+
+    /// Whether `memory(metric)` is RSS standing in for PSS or USS.
+    pub fn memory_is_rss(&self, metric: MemoryMetric) -> bool {
+        matches!(metric, MemoryMetric::Pss | MemoryMetric::Uss) && self.smaps.is_none()
+    }
+
+    /// `~` marks RSS standing in for PSS or USS.
+    pub fn get_memory_display(&self, metric: MemoryMetric) -> String {
+        let mb = format_mb(self.memory(metric));
+        if self.memory_is_rss(metric) {
+            format!("~{}", mb)
         } else {
-            format!("{:.1}MB", self.memory_mb)
+            mb
+        }
+    }
+
+    pub fn get_cpu_display(&self) -> String {
+        match self.cpu_percent {
+            Some(pct) => format!("{:.1}%", pct),
+            None => "—".to_string(),
         }
     }
 
@@ -52,6 +229,19 @@ impl LsofEntry {
     pub fn is_killable(&self) -> bool {
         self.pid.parse::<i32>().is_ok_and(|p| p > 0)
     }
+
+    pub fn exposure(&self) -> Exposure {
+        exposure::classify(self.ip)
+    }
+
+    pub fn id(&self) -> EntryId {
+        EntryId {
+            pid: self.pid.clone(),
+            starttime_ticks: self.starttime_ticks,
+            port: self.port,
+            inode: self.inode,
+        }
+    }
 }
 
 pub fn format_duration(duration: Duration) -> String {
@@ -70,63 +260,261 @@ pub fn format_duration(duration: Duration) -> String {
 const USER_HZ: u64 = 100;
 const TCP_LISTEN: &str = "0A";
 
+/// Connection states worth showing against a listener: live clients
+/// (ESTABLISHED), handshakes in flight (SYN_RECV), and clients that hung
+/// up on a server that hasn't closed its end yet (CLOSE_WAIT — a pile of
+/// these is itself a symptom).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum TcpState {
+    Established,
+    SynRecv,
+    CloseWait,
+}
+
+impl TcpState {
+    fn from_hex(state: &str) -> Option<TcpState> {
+        match state {
+            "01" => Some(TcpState::Established),
+            "03" => Some(TcpState::SynRecv),
+            "08" => Some(TcpState::CloseWait),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TcpState::Established => "ESTABLISHED",
+            TcpState::SynRecv => "SYN_RECV",
+            TcpState::CloseWait => "CLOSE_WAIT",
+        }
+    }
+}
+
+/// A client connection accepted by (or being accepted by) a listener.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Connection {
+    pub local_ip: IpAddr,
+    pub local_port: u16,
+    pub remote_ip: IpAddr,
+    pub remote_port: u16,
+    pub state: TcpState,
+    /// 0 for SYN_RECV: a half-open handshake has no socket inode yet.
+    pub inode: u64,
+}
+
+impl Connection {
+    /// The same TCP connection in another scan — its 4-tuple.
+    pub fn same_as(&self, other: &Connection) -> bool {
+        (
+            self.local_ip,
+            self.local_port,
+            self.remote_ip,
+            self.remote_port,
+        ) == (
+            other.local_ip,
+            other.local_port,
+            other.remote_ip,
+            other.remote_port,
+        )
+    }
+
+    pub fn local(&self) -> SocketAddr {
+        SocketAddr::new(self.local_ip, self.local_port)
+    }
+
+    pub fn remote(&self) -> SocketAddr {
+        SocketAddr::new(self.remote_ip, self.remote_port)
+    }
+
+    /// `203.0.113.7:51234` / `[2001:db8::7]:51234`.
+    pub fn remote_display(&self) -> String {
+        match display_ip(self.remote_ip) {
+            IpAddr::V4(v4) => format!("{}:{}", v4, self.remote_port),
+            IpAddr::V6(v6) => format!("[{}]:{}", v6, self.remote_port),
+        }
+    }
+}
+
+/// A listener's accept queue: how many completed connections are waiting
+/// for `accept()` (rx_queue on a LISTEN row of /proc/net/tcp), against
+/// the backlog passed to `listen()`. The limit isn't in /proc/net/tcp —
+/// tx_queue there is the send queue even for listeners — so it comes from
+/// sock_diag and is `None` when netlink is unavailable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Backlog {
+    pub queued: u32,
+    pub limit: Option<u32>,
+}
+
+impl Backlog {
+    /// Past this share of the limit the server is falling behind on
+    /// `accept()`. Once it's full the kernel starts dropping SYNs, and
+    /// clients see a port that's open but never answers.
+    const NEAR_FULL_PERCENT: u64 = 80;
+
+    pub fn is_near_full(self) -> bool {
+        self.queued > 0
+            && self.limit.is_some_and(|limit| {
+                u64::from(self.queued) * 100 >= u64::from(limit) * Self::NEAR_FULL_PERCENT
+            })
+    }
+
+    /// How full the queue is, for sorting: a 90/100 queue is in worse
+    /// shape than a 100/4096 one. An unknown limit sorts as empty.
+    pub fn fill(self) -> f64 {
+        match self.limit {
+            Some(0) if self.queued > 0 => f64::INFINITY,
+            Some(limit) if limit > 0 => f64::from(self.queued) / f64::from(limit),
+            _ => 0.0,
+        }
+    }
+
+    /// `12/128`, or `12/?` when the limit couldn't be read.
+    pub fn display(self) -> String {
+        match self.limit {
+            Some(limit) => format!("{}/{}", self.queued, limit),
+            None => format!("{}/?", self.queued),
+        }
+    }
+}
+
+/// The address as people think of it: v4-mapped v6 addresses
+/// (::ffff:a.b.c.d) are really IPv4 and are shown as such.
+fn display_ip(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+        v4 => v4,
+    }
+}
+
 #[derive(Debug, PartialEq)]
 struct Listener {
     local_addr: String,
+    ip: IpAddr,
     port: u16,
     inode: u64,
     uid: u32,
     proto: &'static str,
+    backlog: Backlog,
+}
+
+/// The fields reaper uses from one /proc/net/tcp{,6} row, any state.
+struct SocketRow<'a> {
+    local: (IpAddr, u16),
+    remote: (IpAddr, u16),
+    state: &'a str,
+    /// For LISTEN rows, the accept queue length.
+    rx_queue: u32,
+    uid: u32,
+    inode: u64,
+}
+
+fn parse_socket_row(line: &str, is_v6: bool) -> Option<SocketRow<'_>> {
+    let mut it = line.split_whitespace();
+    it.next(); // sl
+    let local = it.next()?;
+    let remote = it.next()?;
+    let state = it.next()?;
+    // tx_queue:rx_queue and tr:tm->when are colon-joined single tokens.
+    // Layout after state: tx_queue:rx_queue, tr:tm->when, retrnsmt, uid,
+    // timeout, inode.
+    let (_tx_queue, rx_queue) = it.next()?.split_once(':')?;
+    let rx_queue = u32::from_str_radix(rx_queue, 16).ok()?;
+    it.next(); // tr:tm->when
+    it.next(); // retrnsmt
+    let uid = it.next()?.parse::<u32>().ok()?;
+    it.next(); // timeout
+    let inode = it.next()?.parse::<u64>().ok()?;
+    Some(SocketRow {
+        local: parse_hex_addr(local, is_v6)?,
+        remote: parse_hex_addr(remote, is_v6)?,
+        state,
+        rx_queue,
+        uid,
+        inode,
+    })
 }
 
 fn parse_proc_net_tcp(content: &str, is_v6: bool) -> Vec<Listener> {
     let mut out = Vec::new();
     for line in content.lines().skip(1) {
-        let mut it = line.split_whitespace();
-        it.next(); // sl
-        let Some(local) = it.next() else { continue };
-        it.next(); // rem_address
-        let Some(state) = it.next() else { continue };
-        if state != TCP_LISTEN {
-            continue;
-        }
-        // tx_queue:rx_queue and tr:tm->when are colon-joined single tokens.
-        // Layout after state: tx_queue:rx_queue, tr:tm->when, retrnsmt, uid,
-        // timeout, inode.
-        it.next(); // tx_queue:rx_queue
-        it.next(); // tr:tm->when
-        it.next(); // retrnsmt
-        let Some(uid_str) = it.next() else { continue };
-        let Ok(uid) = uid_str.parse::<u32>() else {
+        let Some(row) = parse_socket_row(line, is_v6) else {
             continue;
         };
-        it.next(); // timeout
-        let Some(inode_str) = it.next() else { continue };
-        let Ok(inode) = inode_str.parse::<u64>() else {
+        if row.state != TCP_LISTEN {
             continue;
-        };
-        let Some((ip, port)) = parse_hex_addr(local, is_v6) else {
-            continue;
-        };
-        let local_addr = if is_v6 {
-            format!("[{}]", ip)
-        } else if ip == "0.0.0.0" {
-            "*".to_string()
-        } else {
-            ip
+        }
+        let (ip, port) = row.local;
+        // A v6 socket bound to a v4-mapped address (::ffff:a.b.c.d) is
+        // really serving IPv4 — show it the way v4 addresses are shown.
+        let local_addr = match display_ip(ip) {
+            IpAddr::V4(v4) if v4.is_unspecified() && !is_v6 => "*".to_string(),
+            IpAddr::V4(v4) => v4.to_string(),
+            IpAddr::V6(v6) => format!("[{}]", v6),
         };
         out.push(Listener {
             local_addr,
+            ip,
             port,
-            inode,
-            uid,
+            inode: row.inode,
+            uid: row.uid,
             proto: if is_v6 { "TCP6" } else { "TCP" },
+            backlog: Backlog {
+                queued: row.rx_queue,
+                limit: None,
+            },
+        });
+    }
+    out
+}
+
+/// The client-side rows `parse_proc_net_tcp` skips: every socket in a
+/// state that belongs to a served connection.
+fn parse_proc_net_connections(content: &str, is_v6: bool) -> Vec<Connection> {
+    let mut out = Vec::new();
+    for line in content.lines().skip(1) {
+        let Some(row) = parse_socket_row(line, is_v6) else {
+            continue;
+        };
+        let Some(state) = TcpState::from_hex(row.state) else {
+            continue;
+        };
+        out.push(Connection {
+            local_ip: row.local.0,
+            local_port: row.local.1,
+            remote_ip: row.remote.0,
+            remote_port: row.remote.1,
+            state,
+            inode: row.inode,
         });
     }
     out
 }
 
-fn parse_hex_addr(s: &str, is_v6: bool) -> Option<(String, u16)> {
+/// Connections a listener is serving: same address family (a v6 wildcard
+/// socket receives v4 clients too, but they show up in tcp6 as v4-mapped
+/// addresses), same port, and the listener's address — unless it's bound
+/// to every address. With SO_REUSEPORT several listeners match the same
+/// connection; each of them lists it, since only the fd walk could say
+/// which process accepted it.
+///
+/// Sorted by state, then peer, so the list doesn't reshuffle in kernel
+/// hash order on every refresh.
+fn connections_for(listener: &Listener, connections: &[Connection]) -> Vec<Connection> {
+    let mut served: Vec<Connection> = connections
+        .iter()
+        .filter(|c| {
+            c.local_port == listener.port
+                && c.local_ip.is_ipv6() == listener.ip.is_ipv6()
+                && (listener.ip.is_unspecified() || c.local_ip == listener.ip)
+        })
+        .cloned()
+        .collect();
+    served.sort_by_key(|c| (c.state, display_ip(c.remote_ip), c.remote_port));
+    served
+}
+
+fn parse_hex_addr(s: &str, is_v6: bool) -> Option<(IpAddr, u16)> {
     let (addr_hex, port_hex) = s.split_once(':')?;
     let port = u16::from_str_radix(port_hex, 16).ok()?;
     if is_v6 {
@@ -141,7 +529,7 @@ fn parse_hex_addr(s: &str, is_v6: bool) -> Option<(String, u16)> {
         for chunk in bytes.chunks_mut(4) {
             chunk.reverse();
         }
-        Some((Ipv6Addr::from(bytes).to_string(), port))
+        Some((IpAddr::V6(Ipv6Addr::from(bytes)), port))
     } else {
         if addr_hex.len() != 8 {
             return None;
@@ -151,11 +539,11 @@ fn parse_hex_addr(s: &str, is_v6: bool) -> Option<(String, u16)> {
             bytes[i] = u8::from_str_radix(&addr_hex[i * 2..i * 2 + 2], 16).ok()?;
         }
         bytes.reverse();
-        Some((Ipv4Addr::from(bytes).to_string(), port))
+        Some((IpAddr::V4(Ipv4Addr::from(bytes)), port))
     }
 }
 
-fn build_inode_to_pid(needed: &HashSet<u64>) -> HashMap<u64, String> {
+pub(crate) fn build_inode_to_pid(needed: &HashSet<u64>) -> HashMap<u64, String> {
     let mut map = HashMap::new();
     let Ok(entries) = fs::read_dir("/proc") else {
         return map;
@@ -192,6 +580,18 @@ fn build_inode_to_pid(needed: &HashSet<u64>) -> HashMap<u64, String> {
     map
 }
 
+/// Inodes of every TCP socket currently listening, v4 and v6.
+pub(crate) fn listening_inodes() -> HashSet<u64> {
+    ["/proc/net/tcp", "/proc/net/tcp6"]
+        .iter()
+        .flat_map(|path| {
+            let content = fs::read_to_string(path).unwrap_or_default();
+            parse_proc_net_tcp(&content, path.ends_with('6'))
+        })
+        .map(|l| l.inode)
+        .collect()
+}
+
 fn parse_passwd(content: &str) -> HashMap<u32, String> {
     let mut m = HashMap::new();
     for line in content.lines() {
@@ -214,29 +614,73 @@ fn passwd_map() -> &'static HashMap<u32, String> {
     })
 }
 
-fn parse_proc_status(content: &str) -> (Option<u32>, f64) {
+/// (uid, VmRSS MB, VmSwap MB).
+fn parse_proc_status(content: &str) -> (Option<u32>, f64, f64) {
+    let kb = |rest: &str| {
+        rest.split_whitespace()
+            .next()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(0)
+    };
     let mut uid = None;
     let mut rss_kb: u64 = 0;
+    let mut swap_kb: u64 = 0;
     for line in content.lines() {
         if let Some(rest) = line.strip_prefix("Uid:") {
             if let Some(first) = rest.split_whitespace().next() {
                 uid = first.parse::<u32>().ok();
             }
         } else if let Some(rest) = line.strip_prefix("VmRSS:") {
-            rss_kb = rest
-                .split_whitespace()
-                .next()
-                .and_then(|v| v.parse::<u64>().ok())
-                .unwrap_or(0);
+            rss_kb = kb(rest);
+        } else if let Some(rest) = line.strip_prefix("VmSwap:") {
+            swap_kb = kb(rest);
         }
     }
-    (uid, rss_kb as f64 / 1024.0)
+    (uid, rss_kb as f64 / 1024.0, swap_kb as f64 / 1024.0)
 }
 
-fn read_proc_status(pid: &str) -> (Option<u32>, f64) {
+fn read_proc_status(pid: &str) -> (Option<u32>, f64, f64) {
     fs::read_to_string(format!("/proc/{}/status", pid))
         .map(|c| parse_proc_status(&c))
-        .unwrap_or((None, 0.0))
+        .unwrap_or((None, 0.0, 0.0))
+}
+
+fn parse_smaps_rollup(content: &str) -> Option<SmapsRollup> {
+    let mut pss_kb = None;
+    let mut private_kb = 0;
+    let mut swap_kb = 0;
+    for line in content.lines() {
+        let Some((key, rest)) = line.split_once(':') else {
+            continue;
+        };
+        let Some(kb) = rest
+            .split_whitespace()
+            .next()
+            .and_then(|v| v.parse::<u64>().ok())
+        else {
+            continue;
+        };
+        match key {
+            "Pss" => pss_kb = Some(kb),
+            "Private_Clean" | "Private_Dirty" => private_kb += kb,
+            "Swap" => swap_kb = kb,
+            _ => {}
+        }
+    }
+    Some(SmapsRollup {
+        pss_mb: pss_kb? as f64 / 1024.0,
+        uss_mb: private_kb as f64 / 1024.0,
+        swap_mb: swap_kb as f64 / 1024.0,
+    })
+}
+
+/// smaps_rollup walks the process's mappings in the kernel, so it costs
+/// more than /proc/<pid>/status — still cheap at one read per listening
+/// process per scan. Needs the same ptrace access as /proc/<pid>/fd.
+fn read_smaps_rollup(pid: &str) -> Option<SmapsRollup> {
+    fs::read_to_string(format!("/proc/{}/smaps_rollup", pid))
+        .ok()
+        .and_then(|c| parse_smaps_rollup(&c))
 }
 
 #[derive(Debug)]
@@ -244,6 +688,11 @@ struct ProcStat {
     comm: String,
     /// Single-char process state (field 3): 'R', 'S', 'Z', …
     state: Option<char>,
+    /// Field 4: parent PID.
+    ppid: Option<u32>,
+    /// Fields 14+15: utime + stime, CPU time consumed so far in clock
+    /// ticks.
+    cpu_ticks: Option<u64>,
     /// Field 22: process start time in clock ticks since boot.
     starttime_ticks: Option<u64>,
 }
@@ -259,11 +708,16 @@ fn parse_proc_stat(content: &str) -> Option<ProcStat> {
     let comm = content[lparen + 1..rparen].to_string();
     let fields: Vec<&str> = content[rparen + 1..].split_whitespace().collect();
     let state = fields.first().and_then(|s| s.chars().next());
-    // After comm, field 22 (starttime) sits at index 19.
-    let starttime_ticks = fields.get(19).and_then(|s| s.parse::<u64>().ok());
+    let field = |i: usize| fields.get(i).and_then(|s| s.parse::<u64>().ok());
+    // After comm, fields 14/15 (utime/stime) sit at indices 11/12 and
+    // field 22 (starttime) at index 19.
+    let cpu_ticks = field(11).zip(field(12)).map(|(u, s)| u + s);
+    let starttime_ticks = field(19);
     Some(ProcStat {
         comm,
         state,
+        ppid: field(1).map(|p| p as u32),
+        cpu_ticks,
         starttime_ticks,
     })
 }
@@ -274,6 +728,21 @@ fn read_proc_stat(pid: &str) -> Option<ProcStat> {
         .and_then(|c| parse_proc_stat(&c))
 }
 
+/// Single-char state of one process incarnation ('R', 'S', 'D', 'Z', …),
+/// `None` once it's gone — including when its PID now belongs to someone
+/// else.
+pub fn process_state(pid: &str, expected_ticks: Option<u64>) -> Option<char> {
+    read_proc_stat(pid)
+        .filter(|s| s.starttime_ticks == expected_ticks)
+        .and_then(|s| s.state)
+}
+
+/// Start time of whatever currently holds `pid`, to pin an identity for
+/// later `process_state` checks.
+pub fn starttime_ticks(pid: &str) -> Option<u64> {
+    read_proc_stat(pid).and_then(|s| s.starttime_ticks)
+}
+
 fn start_time_from_ticks(ticks: u64, uptime_secs: u64) -> SystemTime {
     let start_secs_after_boot = ticks / USER_HZ;
     let secs_ago = uptime_secs.saturating_sub(start_secs_after_boot);
@@ -372,10 +841,159 @@ struct PidMeta {
     command: String,
     user: String,
     memory_mb: f64,
+    swap_mb: f64,
+    smaps: Option<SmapsRollup>,
+    cpu_percent: Option<f64>,
     start_time: Option<SystemTime>,
     cwd: Option<String>,
 }
 
+/// What restricted-row attribution reads about every process: all of it
+/// world-readable.
+#[derive(Debug)]
+struct ProcInfo {
+    pid: String,
+    ppid: Option<u32>,
+    uid: Option<u32>,
+    comm: String,
+    /// argv, space-joined, for matching.
+    args: String,
+    /// `parse_cmdline`'s form, for display.
+    command: String,
+    starttime_ticks: Option<u64>,
+}
+
+fn read_process_table() -> Vec<ProcInfo> {
+    let Ok(dir) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    dir.flatten()
+        .filter_map(|e| e.file_name().into_string().ok())
+        .filter(|pid| pid.bytes().all(|b| b.is_ascii_digit()))
+        .filter_map(|pid| {
+            let stat = read_proc_stat(&pid)?;
+            // Kernel threads have no argv and own no sockets.
+            let raw = fs::read(format!("/proc/{}/cmdline", pid)).ok()?;
+            let command = parse_cmdline(&raw)?;
+            let (uid, _, _) = read_proc_status(&pid);
+            Some(ProcInfo {
+                args: String::from_utf8_lossy(&raw).replace('\0', " "),
+                ppid: stat.ppid,
+                uid,
+                comm: stat.comm,
+                command,
+                starttime_ticks: stat.starttime_ticks,
+                pid,
+            })
+        })
+        .collect()
+}
+
+/// Servers common enough to be worth recognizing, with the ports they
+/// listen on out of the box.
+const KNOWN_SERVERS: &[(&str, &[u16])] = &[
+    ("sshd", &[22]),
+    ("nginx", &[80, 443]),
+    ("httpd", &[80, 443]),
+    ("apache2", &[80, 443]),
+    ("caddy", &[80, 443, 2019]),
+    ("haproxy", &[]),
+    ("postgres", &[5432]),
+    ("mysqld", &[3306]),
+    ("mariadbd", &[3306]),
+    ("redis-server", &[6379]),
+    ("mongod", &[27017]),
+    ("memcached", &[11211]),
+    ("dnsmasq", &[53]),
+    ("named", &[53]),
+    ("systemd-resolve", &[53]),
+    ("cupsd", &[631]),
+    ("smbd", &[139, 445]),
+    ("vsftpd", &[21]),
+    ("exim4", &[25]),
+    ("dockerd", &[2375, 2376]),
+    ("docker-proxy", &[]),
+    ("php-fpm", &[9000]),
+    ("etcd", &[2379, 2380]),
+    ("prometheus", &[9090]),
+    ("grafana", &[3000]),
+    ("grafana-server", &[3000]),
+];
+
+/// Commands that start servers rather than being them: the shell that ran
+/// `python -m http.server 8000` mentions the port too.
+const LAUNCHERS: &[&str] = &[
+    "sh", "bash", "zsh", "fish", "dash", "sudo", "su", "env", "nohup", "timeout", "tmux", "screen",
+    "watch",
+];
+
+/// Whether `port` appears as its own number in `args`: `--port 5432`,
+/// `-p5432`, `0.0.0.0:5432`, `port=5432` — but not `15432`.
+fn mentions_port(args: &str, port: u16) -> bool {
+    let port = port.to_string();
+    args.split(|c: char| !c.is_ascii_alphanumeric())
+        .map(|t| t.trim_start_matches(|c: char| c.is_ascii_alphabetic()))
+        .any(|t| t == port)
+}
+
+/// Rank `uid`'s processes as owners of its socket on `port`: a command
+/// line naming the port beats a known server that defaults to it, which
+/// beats any known server. A lone process of that user is a candidate on
+/// that alone. Pre-fork workers are folded into their parent, and at most
+/// three candidates are kept.
+fn rank_candidates(uid: u32, port: u16, procs: &[ProcInfo]) -> Vec<Candidate> {
+    let owned: Vec<&ProcInfo> = procs.iter().filter(|p| p.uid == Some(uid)).collect();
+    let mut scored: Vec<(u32, &ProcInfo, String)> = owned
+        .iter()
+        .filter(|p| {
+            // nginx workers under their master, postgres backends under
+            // the postmaster: the parent stands for all of them.
+            !owned.iter().any(|parent| {
+                p.ppid.is_some_and(|ppid| parent.pid == ppid.to_string()) && parent.comm == p.comm
+            })
+        })
+        .filter_map(|p| {
+            let argv0 = p.args.split(' ').next().map(basename).unwrap_or_default();
+            let known = KNOWN_SERVERS
+                .iter()
+                .find(|(name, _)| *name == p.comm || *name == argv0);
+            let mut score = 0;
+            let mut hints = Vec::new();
+            if mentions_port(&p.args, port) && !LAUNCHERS.contains(&p.comm.as_str()) {
+                score += 4;
+                hints.push(format!("its command line mentions {}", port));
+            }
+            if let Some((name, ports)) = known {
+                if ports.contains(&port) {
+                    score += 3;
+                    hints.push(format!("{} usually listens on {}", name, port));
+                } else {
+                    score += 1;
+                    hints.push(format!("{} is a server", name));
+                }
+            }
+            if owned.len() == 1 {
+                score += 1;
+                hints.push("the user's only process".to_string());
+            }
+            (score > 0).then(|| (score, *p, hints.swap_remove(0)))
+        })
+        .collect();
+    // Best first; among equals the oldest, which is likelier the one
+    // that bound the port.
+    scored.sort_by_key(|(score, p, _)| (std::cmp::Reverse(*score), p.starttime_ticks));
+    scored
+        .into_iter()
+        .take(3)
+        .map(|(_, p, hint)| Candidate {
+            pid: p.pid.clone(),
+            starttime_ticks: p.starttime_ticks,
+            command: p.command.clone(),
+            hint,
+        })
+        .collect()
+}
+
 /// Carries the socket-inode → PID mapping across scans.
 ///
 /// Resolving an inode to its owning PID means walking every fd of every
@@ -392,22 +1010,56 @@ struct PidMeta {
 /// /proc for sockets that can never resolve. Cached misses are retried
 /// for free whenever a genuinely new inode forces a walk anyway, so a
 /// transient miss (a socket caught mid-handoff) heals itself.
+///
+/// `cpu_samples` holds each process incarnation's cumulative CPU ticks
+/// from the previous scan — CPU% only exists as a delta between two
+/// readings. Keyed by pid *and* starttime so a recycled PID starts a
+/// fresh baseline instead of inheriting a bogus delta.
 #[derive(Debug, Default)]
 pub struct Scanner {
     inode_to_pid: HashMap<u64, (String, Option<u64>)>,
     unresolved: HashSet<u64>,
+    cpu_samples: HashMap<(String, Option<u64>), (u64, Instant)>,
+    /// Candidates per restricted inode, ranked when the inode first shows
+    /// up; later scans only drop the ones that have exited.
+    probable: HashMap<u64, Vec<Candidate>>,
+    /// A privileged `reaper helper` to resolve and kill what this process
+    /// can't. Dropped on the first I/O error — the helper is gone then.
+    pub(crate) helper: Option<Helper>,
+    /// With `--connect`: scans and kills come from `reaper agent`
+    /// instead of this process's own /proc walk.
+    pub(crate) agent: Option<agent::Client>,
 }
 
 impl Scanner {
     pub fn scan(&mut self) -> Vec<LsofEntry> {
+        self.try_scan().unwrap_or_default()
+    }
+
+    /// Like `scan`, but an unreachable agent is an error rather than an
+    /// empty scan, so the caller can keep what it last saw. The error is
+    /// also kept on the client for the header; the next scan reconnects.
+    pub fn try_scan(&mut self) -> io::Result<Vec<LsofEntry>> {
+        if let Some(agent) = &mut self.agent {
+            return agent.list();
+        }
         let tcp = fs::read_to_string("/proc/net/tcp").unwrap_or_default();
         let tcp6 = fs::read_to_string("/proc/net/tcp6").unwrap_or_default();
         let mut listeners = parse_proc_net_tcp(&tcp, false);
         listeners.extend(parse_proc_net_tcp(&tcp6, true));
+        let mut connections = parse_proc_net_connections(&tcp, false);
+        connections.extend(parse_proc_net_connections(&tcp6, true));
+        if let Ok(queues) = sock_diag::listen_queues() {
+            for l in &mut listeners {
+                l.backlog.limit = queues.get(&l.inode).map(|q| q.limit);
+            }
+        }
 
         let needed: HashSet<u64> = listeners.iter().map(|l| l.inode).collect();
         self.inode_to_pid.retain(|inode, _| needed.contains(inode));
         self.unresolved.retain(|inode| needed.contains(inode));
+        self.probable
+            .retain(|inode, _| self.unresolved.contains(inode));
 
         // One stat read per distinct PID per scan — the identity check
         // here, comm/start-time for the entries below.
@@ -439,7 +1091,21 @@ impl Scanner {
             .collect();
         if !unknown.is_empty() {
             let candidates: HashSet<u64> = unknown.union(&self.unresolved).copied().collect();
-            let resolved = build_inode_to_pid(&candidates);
+            let mut resolved = build_inode_to_pid(&candidates);
+            // Whatever we couldn't read ourselves, the helper may.
+            if resolved.len() < candidates.len()
+                && let Some(helper) = &mut self.helper
+            {
+                let missing: Vec<u64> = candidates
+                    .iter()
+                    .filter(|inode| !resolved.contains_key(inode))
+                    .copied()
+                    .collect();
+                match helper.resolve(&missing) {
+                    Ok(owners) => resolved.extend(owners),
+                    Err(_) => self.helper = None,
+                }
+            }
             for inode in &candidates {
                 match resolved.get(inode) {
                     Some(pid) => {
@@ -459,21 +1125,35 @@ impl Scanner {
 
         let uptime = boot_uptime_secs();
         let passwd = passwd_map();
+        let interfaces = InterfaceTable::load();
         // Scan-local, so a PID backing several listeners (v4+v6) is only
         // read once per scan.
         let mut pid_cache: HashMap<String, PidMeta> = HashMap::new();
+        let now = Instant::now();
+        let mut cpu_samples = HashMap::new();
+        // Only read when a restricted row needs ranking, at most once.
+        let mut process_table: Option<Vec<ProcInfo>> = None;
 
         let mut entries = Vec::new();
         for l in listeners {
             let pid = self.inode_to_pid.get(&l.inode).map(|(p, _)| p.clone());
+            let interface = interfaces.owner(l.ip).map(str::to_string);
+            let served = connections_for(&l, &connections);
 
             let entry = match pid {
                 Some(pid) => {
                     let meta = pid_cache.entry(pid.clone()).or_insert_with(|| {
                         let stat = stats.remove(&pid).flatten();
                         let ticks = stat.as_ref().and_then(|s| s.starttime_ticks);
+                        let cpu_percent = stat.as_ref().and_then(|s| s.cpu_ticks).and_then(|cpu| {
+                            let key = (pid.clone(), ticks);
+                            let previous = self.cpu_samples.get(&key).copied();
+                            cpu_samples.insert(key, (cpu, now));
+                            let (prev_cpu, prev_at) = previous?;
+                            cpu_percent(cpu.saturating_sub(prev_cpu), now.duration_since(prev_at))
+                        });
                         let comm = stat.map(|s| s.comm).unwrap_or_default();
-                        let (uid_opt, memory_mb) = read_proc_status(&pid);
+                        let (uid_opt, memory_mb, swap_mb) = read_proc_status(&pid);
                         let user = uid_opt
                             .map(|uid| resolve_user(uid, passwd))
                             .unwrap_or_else(|| "?".to_string());
@@ -490,6 +1170,9 @@ impl Scanner {
                             command,
                             user,
                             memory_mb,
+                            swap_mb,
+                            smaps: read_smaps_rollup(&pid),
+                            cpu_percent,
                             start_time,
                             cwd,
                         }
@@ -499,12 +1182,21 @@ impl Scanner {
                         pid: pid.clone(),
                         user: meta.user.clone(),
                         local_addr: l.local_addr,
+                        ip: l.ip,
+                        interface,
                         port: l.port,
                         protocol: l.proto,
                         memory_mb: meta.memory_mb,
+                        swap_mb: meta.swap_mb,
+                        smaps: meta.smaps,
+                        cpu_percent: meta.cpu_percent,
                         start_time: meta.start_time,
                         starttime_ticks: meta.starttime_ticks,
                         cwd: meta.cwd.clone(),
+                        inode: l.inode,
+                        connections: served,
+                        backlog: l.backlog,
+                        probable: Vec::new(),
                     }
                 }
                 None => {
@@ -513,25 +1205,311 @@ impl Scanner {
                     // /proc/net/tcp row itself still tells us who owns the
                     // socket — show that, even though we can't resolve the
                     // actual command name.
+                    let probable = match self.probable.get_mut(&l.inode) {
+                        Some(cached) => {
+                            cached.retain(|c| starttime_ticks(&c.pid) == c.starttime_ticks);
+                            cached.clone()
+                        }
+                        None => {
+                            let table = process_table.get_or_insert_with(read_process_table);
+                            let ranked = rank_candidates(l.uid, l.port, table);
+                            self.probable.insert(l.inode, ranked.clone());
+                            ranked
+                        }
+                    };
                     LsofEntry {
                         command: "(restricted)".to_string(),
                         pid: "?".to_string(),
                         user: resolve_user(l.uid, passwd),
                         local_addr: l.local_addr,
+                        ip: l.ip,
+                        interface,
                         port: l.port,
                         protocol: l.proto,
                         memory_mb: 0.0,
+                        swap_mb: 0.0,
+                        smaps: None,
+                        cpu_percent: None,
                         start_time: None,
                         starttime_ticks: None,
                         cwd: None,
+                        inode: l.inode,
+                        connections: served,
+                        backlog: l.backlog,
+                        probable,
                     }
                 }
             };
             entries.push(entry);
         }
 
-        entries
+        // Replacing (not merging) drops the baselines of processes that
+        // no longer hold a listener.
+        self.cpu_samples = cpu_samples;
+        Ok(entries)
+    }
+
+    /// `kill_process_verified`, handing over to the helper when the
+    /// process belongs to someone we may not signal.
+    pub fn kill_verified(&mut self, p: &LsofEntry) -> io::Result<KillOutcome> {
+        if let Some(agent) = &mut self.agent {
+            return agent.kill(p);
+        }
+        let outcome = kill_process_verified(&p.pid, p.starttime_ticks);
+        match (&outcome, &mut self.helper) {
+            (Err(e), Some(helper)) if e.kind() == io::ErrorKind::PermissionDenied => {
+                let Some(ticks) = p.starttime_ticks else {
+                    return outcome;
+                };
+                let result = helper.kill(p, ticks);
+                if result
+                    .as_ref()
+                    .is_err_and(|e| e.kind() == io::ErrorKind::BrokenPipe)
+                {
+                    self.helper = None;
+                }
+                result
+            }
+            _ => outcome,
+        }
+    }
+
+    /// Who listens on `port` right now: its /proc/net/tcp{,6} rows, with
+    /// just those sockets' owners looked up afresh. Rows carry no memory,
+    /// CPU or connections. Unlike `scan()` this leaves the caches and CPU
+    /// baselines alone — polled right after a kill, a scan would reset
+    /// every row's CPU sample to a few milliseconds' worth.
+    fn probe_port(&mut self, port: u16) -> Vec<LsofEntry> {
+        let listeners = port_listeners(port);
+        if listeners.is_empty() {
+            return Vec::new();
+        }
+        let inodes: HashSet<u64> = listeners.iter().map(|l| l.inode).collect();
+        let mut owners = build_inode_to_pid(&inodes);
+        if owners.len() < inodes.len()
+            && let Some(helper) = &mut self.helper
+        {
+            let missing: Vec<u64> = inodes
+                .iter()
+                .filter(|inode| !owners.contains_key(inode))
+                .copied()
+                .collect();
+            match helper.resolve(&missing) {
+                Ok(found) => owners.extend(found),
+                Err(_) => self.helper = None,
+            }
+        }
+        let passwd = passwd_map();
+        listeners
+            .into_iter()
+            .map(|l| {
+                let pid = owners.get(&l.inode).cloned();
+                let command = pid
+                    .as_deref()
+                    .and_then(|pid| {
+                        read_proc_cmdline(pid).or_else(|| read_proc_stat(pid).map(|s| s.comm))
+                    })
+                    .unwrap_or_else(|| "(restricted)".to_string());
+                LsofEntry {
+                    command,
+                    pid: pid.unwrap_or_else(|| "?".to_string()),
+                    user: resolve_user(l.uid, passwd),
+                    local_addr: l.local_addr,
+                    ip: l.ip,
+                    interface: None,
+                    port: l.port,
+                    protocol: l.proto,
+                    memory_mb: 0.0,
+                    swap_mb: 0.0,
+                    smaps: None,
+                    cpu_percent: None,
+                    start_time: None,
+                    starttime_ticks: None,
+                    cwd: None,
+                    inode: l.inode,
+                    connections: Vec::new(),
+                    backlog: l.backlog,
+                    probable: Vec::new(),
+                }
+            })
+            .collect()
+    }
+
+    /// After `killed`'s process has exited, find out what became of its
+    /// port. A child that inherited the socket shows up at once; a
+    /// supervisor's replacement needs a moment to start and bind, so the
+    /// port is re-probed for up to `RESPAWN_WINDOW` before it's declared
+    /// free. `before` is `port_inodes()` from just before the kill: those
+    /// sockets' holders (SO_REUSEPORT siblings) were there all along.
+    ///
+    /// Through an agent, the agent has already looked — its own scan is
+    /// the one that sees the port.
+    pub fn await_port_release(&mut self, killed: &LsofEntry, before: &HashSet<u64>) -> PortOutcome {
+        if let Some(agent) = &mut self.agent {
+            return agent.port.take().unwrap_or(PortOutcome::PortReleased);
+        }
+        let deadline = Instant::now() + RESPAWN_WINDOW;
+        loop {
+            let holders = self.probe_port(killed.port);
+            if let Some(outcome) = port_outcome(killed, before, &holders) {
+                return outcome;
+            }
+            if Instant::now() >= deadline {
+                return PortOutcome::PortReleased;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    }
+}
+
+/// How long a killed listener's port is watched for a replacement.
+/// systemd restarts after 100ms by default; most dev-server watchers
+/// rebind faster still.
+const RESPAWN_WINDOW: Duration = Duration::from_millis(500);
+
+/// What holds a killed listener's port after its process exited.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PortOutcome {
+    /// Nothing listens there any more.
+    PortReleased,
+    /// The very same socket lives on in another process (pid, command) —
+    /// a child or fork sibling that inherited the fd.
+    PortStillHeldBy(String, String),
+    /// A new socket on the same address, from a new process (pid,
+    /// command) — a supervisor or file watcher restarted the server.
+    Respawned(String, String),
+}
+
+/// The /proc/net/tcp{,6} listeners on `port`.
+fn port_listeners(port: u16) -> Vec<Listener> {
+    [("/proc/net/tcp", false), ("/proc/net/tcp6", true)]
+        .into_iter()
+        .flat_map(|(path, v6)| {
+            parse_proc_net_tcp(&fs::read_to_string(path).unwrap_or_default(), v6)
+        })
+        .filter(|l| l.port == port)
+        .collect()
+}
+
+/// The sockets listening on `port` right now — taken before a kill, for
+/// `await_port_release` to tell a respawn from a sibling.
+pub fn port_inodes(port: u16) -> HashSet<u64> {
+    port_listeners(port).iter().map(|l| l.inode).collect()
+}
+
+/// `None` while the port is free — or held only by sockets in `before`,
+/// which were listening next to the killed one all along: it may yet be
+/// re-bound.
+fn port_outcome(
+    killed: &LsofEntry,
+    before: &HashSet<u64>,
+    scan: &[LsofEntry],
+) -> Option<PortOutcome> {
+    // A merged TCP4/6 row stands for both families and both binds.
+    let merged = killed.protocol == "TCP4/6";
+    let holders: Vec<&LsofEntry> = scan
+        .iter()
+        .filter(|p| {
+            p.port == killed.port
+                && (merged || (p.protocol == killed.protocol && p.local_addr == killed.local_addr))
+        })
+        .collect();
+    let owner = |p: &LsofEntry| (p.pid.clone(), p.command.clone());
+    if let Some(heir) = holders.iter().find(|p| p.inode == killed.inode) {
+        let (pid, command) = owner(heir);
+        return Some(PortOutcome::PortStillHeldBy(pid, command));
+    }
+    let fresh = holders.iter().find(|p| !before.contains(&p.inode))?;
+    let (pid, command) = owner(fresh);
+    Some(PortOutcome::Respawned(pid, command))
+}
+
+/// CPU time consumed over a wall-clock interval, as a percent of one
+/// core. `None` for an interval too short to measure meaningfully.
+fn cpu_percent(delta_ticks: u64, elapsed: Duration) -> Option<f64> {
+    let secs = elapsed.as_secs_f64();
+    if secs < 0.01 {
+        return None;
+    }
+    Some(delta_ticks as f64 / USER_HZ as f64 / secs * 100.0)
+}
+
+/// Fold each process's IPv4 + IPv6 listener pair on one port into a
+/// single `TCP4/6` row. Most servers bind both `0.0.0.0` and `[::]`, so
+/// without this every service is listed twice.
+///
+/// Only an exact pair merges: one `TCP` and one `TCP6` socket held by the
+/// same process incarnation on the same port. Anything else (three
+/// sockets, restricted rows whose owner we can't prove) stays as-is. The
+/// merged row keeps the more exposed of the two binds as its address, so
+/// `127.0.0.1` + `[::]` is still flagged as a wildcard listener.
+pub fn merge_dual_stack(entries: &[LsofEntry]) -> Vec<LsofEntry> {
+    let mut groups: HashMap<(&str, Option<u64>, u16), Vec<usize>> = HashMap::new();
+    for (i, p) in entries.iter().enumerate() {
+        if p.is_killable() {
+            groups
+                .entry((p.pid.as_str(), p.starttime_ticks, p.port))
+                .or_default()
+                .push(i);
+        }
+    }
+
+    let mut merged_into: HashMap<usize, LsofEntry> = HashMap::new();
+    let mut absorbed: HashSet<usize> = HashSet::new();
+    for members in groups.values() {
+        let &[a, b] = members.as_slice() else {
+            continue;
+        };
+        let (v4, v6) = match (entries[a].protocol, entries[b].protocol) {
+            ("TCP", "TCP6") => (&entries[a], &entries[b]),
+            ("TCP6", "TCP") => (&entries[b], &entries[a]),
+            _ => continue,
+        };
+        let mut row = v4.clone();
+        row.protocol = "TCP4/6";
+        row.local_addr = if v4.local_addr == "*" && v6.ip.is_unspecified() {
+            "*".to_string()
+        } else {
+            format!("{}, {}", v4.local_addr, v6.local_addr)
+        };
+        if v6.exposure() > v4.exposure() {
+            row.ip = v6.ip;
+            row.interface = v6.interface.clone();
+        }
+        row.connections.extend(v6.connections.iter().cloned());
+        // Two sockets, two queues: show whichever is closer to full.
+        if v6.backlog.fill() > v4.backlog.fill() {
+            row.backlog = v6.backlog;
+        }
+        // The row stands where the first of the pair stood.
+        merged_into.insert(a.min(b), row);
+        absorbed.insert(a.max(b));
     }
+
+    entries
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !absorbed.contains(i))
+        .map(|(i, p)| merged_into.remove(&i).unwrap_or_else(|| p.clone()))
+        .collect()
+}
+
+/// Name of `uid`, for callers outside a scan.
+pub fn user_name(uid: u32) -> String {
+    resolve_user(uid, passwd_map())
+}
+
+/// gid of the group called `name`, from /etc/group.
+pub(crate) fn group_id(name: &str) -> Option<u32> {
+    fs::read_to_string("/etc/group")
+        .ok()?
+        .lines()
+        .find_map(|l| {
+            let mut fields = l.split(':');
+            (fields.next()? == name).then_some(())?;
+            fields.nth(1)?.parse().ok()
+        })
 }
 
 /// uid → username via /etc/passwd, falling back to the numeric uid.
@@ -539,9 +1517,13 @@ fn resolve_user(uid: u32, passwd: &HashMap<u32, String>) -> String {
     passwd.get(&uid).cloned().unwrap_or_else(|| uid.to_string())
 }
 
-/// Outcome of attempting to terminate a process.
-#[derive(Debug)]
+/// Outcome of attempting to terminate a process. Serialized as its
+/// `label()`, which is how the privileged helper reports it.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum KillOutcome {
+    /// It was gone before any signal was sent.
+    AlreadyExited,
     /// Process terminated within the verification window.
     Terminated,
     /// SIGTERM was ignored; SIGKILL got it.
@@ -550,6 +1532,71 @@ pub enum KillOutcome {
     StillAlive,
 }
 
+impl KillOutcome {
+    /// Signals `kill_process_verified` sent to reach this outcome.
+    pub fn signals_sent(&self) -> &'static [&'static str] {
+        match self {
+            KillOutcome::AlreadyExited => &[],
+            KillOutcome::Terminated => &["SIGTERM"],
+            KillOutcome::ForceKilled | KillOutcome::StillAlive => &["SIGTERM", "SIGKILL"],
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            KillOutcome::AlreadyExited => "already-exited",
+            KillOutcome::Terminated => "terminated",
+            KillOutcome::ForceKilled => "force-killed",
+            KillOutcome::StillAlive => "still-alive",
+        }
+    }
+}
+
+/// A kill that failed after SIGTERM was already sent — SIGKILL couldn't
+/// be delivered. Carried inside the `io::Error`, so the audit record
+/// still says which signals reached the process.
+#[derive(Debug)]
+pub struct PartialKill {
+    pub signals: &'static [&'static str],
+    source: io::Error,
+}
+
+impl std::fmt::Display for PartialKill {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.source.fmt(f)
+    }
+}
+
+impl std::error::Error for PartialKill {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+impl PartialKill {
+    /// `e` from sending SIGKILL. ESRCH means the process exited on its
+    /// own after SIGTERM, between the grace period and the SIGKILL.
+    pub(crate) fn after_sigterm(e: io::Error) -> io::Result<KillOutcome> {
+        if e.raw_os_error() == Some(libc::ESRCH) {
+            return Ok(KillOutcome::Terminated);
+        }
+        Err(io::Error::new(
+            e.kind(),
+            PartialKill {
+                signals: &["SIGTERM"],
+                source: e,
+            },
+        ))
+    }
+
+    /// The signals sent before `e`, if it's a `PartialKill`.
+    pub fn signals_in(e: &io::Error) -> &'static [&'static str] {
+        e.get_ref()
+            .and_then(|inner| inner.downcast_ref::<PartialKill>())
+            .map_or(&[], |p| p.signals)
+    }
+}
+
 /// An open pidfd (Linux 5.3+): a handle to one specific process
 /// incarnation. Signals sent through it can never reach a recycled PID,
 /// and it polls readable the moment the process terminates.
@@ -643,13 +1690,13 @@ pub fn kill_process_verified(pid: &str, expected_ticks: Option<u64>) -> io::Resu
         Ok(fd) => Some(fd),
         Err(e) => match e.raw_os_error() {
             // Already gone — the goal state, nothing to signal.
-            Some(libc::ESRCH) => return Ok(KillOutcome::Terminated),
+            Some(libc::ESRCH) => return Ok(KillOutcome::AlreadyExited),
             Some(libc::ENOSYS) => None,
             _ => return Err(e),
         },
     };
     match read_proc_stat(pid).and_then(|s| s.starttime_ticks) {
-        None => return Ok(KillOutcome::Terminated),
+        None => return Ok(KillOutcome::AlreadyExited),
         Some(t) if t != expected => {
             return Err(io::Error::other(
                 "PID was recycled by a different process; not killing",
@@ -663,7 +1710,9 @@ pub fn kill_process_verified(pid: &str, expected_ticks: Option<u64>) -> io::Resu
             if fd.wait_exit(Duration::from_millis(200)) {
                 return Ok(KillOutcome::Terminated);
             }
-            fd.send_signal(libc::SIGKILL)?;
+            if let Err(e) = fd.send_signal(libc::SIGKILL) {
+                return PartialKill::after_sigterm(e);
+            }
             if fd.wait_exit(Duration::from_millis(200)) {
                 return Ok(KillOutcome::ForceKilled);
             }
@@ -674,7 +1723,9 @@ pub fn kill_process_verified(pid: &str, expected_ticks: Option<u64>) -> io::Resu
             if wait_for_exit(pid, Duration::from_millis(200)) {
                 return Ok(KillOutcome::Terminated);
             }
-            send_signal(pid, libc::SIGKILL)?;
+            if let Err(e) = send_signal(pid, libc::SIGKILL) {
+                return PartialKill::after_sigterm(e);
+            }
             if wait_for_exit(pid, Duration::from_millis(200)) {
                 return Ok(KillOutcome::ForceKilled);
             }
@@ -735,17 +1786,17 @@ mod tests {
         // 127.0.0.1:8080 (kernel formats addr as LE u32 hex)
         assert_eq!(
             parse_hex_addr("0100007F:1F90", false),
-            Some(("127.0.0.1".to_string(), 8080))
+            Some(("127.0.0.1".parse().unwrap(), 8080))
         );
         // 0.0.0.0:80
         assert_eq!(
             parse_hex_addr("00000000:0050", false),
-            Some(("0.0.0.0".to_string(), 80))
+            Some(("0.0.0.0".parse().unwrap(), 80))
         );
         // 192.168.1.1:443
         assert_eq!(
             parse_hex_addr("0101A8C0:01BB", false),
-            Some(("192.168.1.1".to_string(), 443))
+            Some(("192.168.1.1".parse().unwrap(), 443))
         );
     }
 
@@ -754,17 +1805,17 @@ mod tests {
         // :: with port 8080
         assert_eq!(
             parse_hex_addr("00000000000000000000000000000000:1F90", true),
-            Some(("::".to_string(), 8080))
+            Some(("::".parse().unwrap(), 8080))
         );
         // ::1 with port 80
         assert_eq!(
             parse_hex_addr("00000000000000000000000001000000:0050", true),
-            Some(("::1".to_string(), 80))
+            Some(("::1".parse().unwrap(), 80))
         );
         // 2001:db8:: with port 80 — first 4-byte group is LE per the kernel format
         assert_eq!(
             parse_hex_addr("B80D0120000000000000000000000000:0050", true),
-            Some(("2001:db8::".to_string(), 80))
+            Some(("2001:db8::".parse().unwrap(), 80))
         );
     }
 
@@ -798,6 +1849,196 @@ mod tests {
         assert_eq!(listeners[1].uid, 0);
     }
 
+    #[test]
+    fn parse_proc_net_tcp_reads_accept_queue() {
+        // rx_queue on a LISTEN row is the accept queue; tx_queue is not
+        // the backlog, whatever it holds.
+        let content = "\
+  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:1F90 00000000:0000 0A 00000000:0000000C 00:00000000 00000000  1000        0 318513 1 0000000000000000
+";
+        let listeners = parse_proc_net_tcp(content, false);
+        assert_eq!(
+            listeners[0].backlog,
+            Backlog {
+                queued: 12,
+                limit: None
+            }
+        );
+        assert_eq!(listeners[0].backlog.display(), "12/?");
+    }
+
+    #[test]
+    fn port_outcome_tells_inherited_from_respawned() {
+        let killed = listener_entry("10", 8080, "TCP", "0.0.0.0");
+        let before = HashSet::from([killed.inode]);
+        let other_port = listener_entry("20", 3000, "TCP", "0.0.0.0");
+        assert_eq!(port_outcome(&killed, &before, &[other_port]), None);
+
+        // Same socket inode, new owner: a child inherited it.
+        let inherited = listener_entry("11", 8080, "TCP", "0.0.0.0");
+        assert_eq!(
+            port_outcome(&killed, &before, &[inherited]),
+            Some(PortOutcome::PortStillHeldBy("11".into(), "server".into()))
+        );
+
+        let mut rebound = listener_entry("12", 8080, "TCP", "0.0.0.0");
+        rebound.inode = 99;
+        assert_eq!(
+            port_outcome(&killed, &before, &[rebound]),
+            Some(PortOutcome::Respawned("12".into(), "server".into()))
+        );
+
+        // Another address on the same port is a different listener.
+        let elsewhere = listener_entry("13", 8080, "TCP", "127.0.0.1");
+        assert_eq!(port_outcome(&killed, &before, &[elsewhere]), None);
+
+        // An SO_REUSEPORT sibling that was listening all along isn't a
+        // respawn — but a new socket next to it is.
+        let mut sibling = listener_entry("14", 8080, "TCP", "0.0.0.0");
+        sibling.inode = 50;
+        let with_sibling = HashSet::from([killed.inode, sibling.inode]);
+        assert_eq!(
+            port_outcome(&killed, &with_sibling, &[sibling.clone()]),
+            None
+        );
+        let mut respawned = listener_entry("15", 8080, "TCP", "0.0.0.0");
+        respawned.inode = 51;
+        assert_eq!(
+            port_outcome(&killed, &with_sibling, &[sibling, respawned]),
+            Some(PortOutcome::Respawned("15".into(), "server".into()))
+        );
+    }
+
+    #[test]
+    fn backlog_near_full_threshold() {
+        let b = |queued, limit| Backlog { queued, limit };
+        assert!(!b(0, Some(0)).is_near_full());
+        assert!(!b(0, Some(128)).is_near_full());
+        assert!(!b(101, Some(128)).is_near_full());
+        assert!(b(103, Some(128)).is_near_full());
+        // The kernel lets the queue run one past the limit before dropping.
+        assert!(b(129, Some(128)).is_near_full());
+        assert!(b(1, Some(0)).is_near_full());
+        assert!(!b(500, None).is_near_full());
+        assert_eq!(b(12, Some(128)).display(), "12/128");
+    }
+
+    #[test]
+    fn parse_proc_net_tcp6_shows_v4_mapped_as_dotted() {
+        // ::ffff:127.0.0.1 port 8080
+        let content = "\
+  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0000000000000000FFFF00000100007F:1F90 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 26191 1
+";
+        let listeners = parse_proc_net_tcp(content, true);
+        assert_eq!(listeners[0].local_addr, "127.0.0.1");
+        assert_eq!(listeners[0].proto, "TCP6");
+    }
+
+    fn listener_entry(pid: &str, port: u16, protocol: &'static str, ip: &str) -> LsofEntry {
+        let ip: IpAddr = ip.parse().unwrap();
+        LsofEntry {
+            local_addr: match ip {
+                IpAddr::V4(v4) if v4.is_unspecified() => "*".to_string(),
+                IpAddr::V4(v4) => v4.to_string(),
+                IpAddr::V6(v6) => format!("[{}]", v6),
+            },
+            ip,
+            protocol,
+            ..LsofEntry::test(pid, port, port.into())
+        }
+    }
+
+    #[test]
+    fn merge_dual_stack_folds_v4_v6_pairs() {
+        let entries = vec![
+            listener_entry("10", 80, "TCP", "0.0.0.0"),
+            listener_entry("20", 22, "TCP", "0.0.0.0"),
+            listener_entry("10", 80, "TCP6", "::"),
+            listener_entry("30", 5432, "TCP", "127.0.0.1"),
+            listener_entry("30", 5432, "TCP6", "::"),
+        ];
+        let merged = merge_dual_stack(&entries);
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged[0].protocol, "TCP4/6");
+        assert_eq!(merged[0].local_addr, "*");
+        assert_eq!(merged[1].protocol, "TCP");
+        // The more exposed bind decides how the merged row is flagged.
+        assert_eq!(merged[2].local_addr, "127.0.0.1, [::]");
+        assert_eq!(merged[2].exposure(), Exposure::Wildcard);
+    }
+
+    #[test]
+    fn merge_dual_stack_leaves_other_owners_apart() {
+        let mut other = listener_entry("11", 80, "TCP6", "::");
+        other.starttime_ticks = Some(2);
+        let entries = vec![listener_entry("10", 80, "TCP", "0.0.0.0"), other];
+        assert_eq!(merge_dual_stack(&entries).len(), 2);
+
+        let mut restricted = listener_entry("?", 80, "TCP", "0.0.0.0");
+        restricted.starttime_ticks = None;
+        let mut restricted6 = listener_entry("?", 80, "TCP6", "::");
+        restricted6.starttime_ticks = None;
+        assert_eq!(merge_dual_stack(&[restricted, restricted6]).len(), 2);
+    }
+
+    #[test]
+    fn parse_proc_net_connections_keeps_served_states() {
+        let content = "\
+  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 318513 2 0000000000000000
+   1: 0100007F:1F90 0100007F:ABCD 01 00000000:00000000 00:00000000 00000000  1000        0 999999 1 0000000000000000
+   2: 0100007F:1F90 0200007F:ABCE 08 00000000:00000000 00:00000000 00000000  1000        0 999998 1 0000000000000000
+   3: 0100007F:1F90 0300007F:ABCF 06 00000000:00000000 00:00000000 00000000  1000        0 0 1 0000000000000000
+";
+        let connections = parse_proc_net_connections(content, false);
+        assert_eq!(connections.len(), 2);
+        assert_eq!(connections[0].state, TcpState::Established);
+        assert_eq!(connections[0].remote_display(), "127.0.0.1:43981");
+        assert_eq!(connections[0].inode, 999999);
+        assert_eq!(connections[1].state, TcpState::CloseWait);
+    }
+
+    #[test]
+    fn connections_match_listener_port_family_and_address() {
+        let conn = |local: &str, port: u16, remote: &str| Connection {
+            local_ip: local.parse().unwrap(),
+            local_port: port,
+            remote_ip: remote.parse().unwrap(),
+            remote_port: 40000,
+            state: TcpState::Established,
+            inode: 1,
+        };
+        let connections = vec![
+            conn("127.0.0.1", 8080, "127.0.0.1"),
+            conn("10.0.0.5", 8080, "10.0.0.9"),
+            conn("10.0.0.5", 9090, "10.0.0.9"),
+            conn("::ffff:10.0.0.5", 8080, "::ffff:10.0.0.9"),
+        ];
+        let listener = |ip: &str, proto| Listener {
+            local_addr: String::new(),
+            ip: ip.parse().unwrap(),
+            port: 8080,
+            inode: 1,
+            uid: 0,
+            proto,
+            backlog: Backlog::default(),
+        };
+        assert_eq!(
+            connections_for(&listener("0.0.0.0", "TCP"), &connections).len(),
+            2
+        );
+        assert_eq!(
+            connections_for(&listener("127.0.0.1", "TCP"), &connections).len(),
+            1
+        );
+        // The v6 wildcard socket serves the v4-mapped client.
+        let v6 = connections_for(&listener("::", "TCP6"), &connections);
+        assert_eq!(v6.len(), 1);
+        assert_eq!(v6[0].remote_display(), "10.0.0.9:40000");
+    }
+
     #[test]
     fn parse_proc_net_tcp6_formats_v6() {
         let content = "\
@@ -845,6 +2086,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_proc_stat_sums_utime_and_stime() {
+        // stat_line numbers the filler fields by index, so utime (index
+        // 11) = 11 and stime (index 12) = 12.
+        let stat = parse_proc_stat(&stat_line("server", "S", "100")).unwrap();
+        assert_eq!(stat.cpu_ticks, Some(23));
+    }
+
+    #[test]
+    fn cpu_percent_is_relative_to_one_core() {
+        // 50 ticks at USER_HZ=100 over 1s = half a core.
+        assert_eq!(cpu_percent(50, Duration::from_secs(1)), Some(50.0));
+        // Two busy cores over 2s.
+        assert_eq!(cpu_percent(400, Duration::from_secs(2)), Some(200.0));
+        assert_eq!(cpu_percent(5, Duration::ZERO), None);
+    }
+
     #[test]
     fn parse_proc_stat_extracts_zombie_state() {
         let stat = parse_proc_stat(&stat_line("dead-server", "Z", "42")).unwrap();
@@ -867,10 +2125,55 @@ mod tests {
 Name:\tnginx
 Uid:\t33\t33\t33\t33
 VmRSS:\t  4096 kB
+VmSwap:\t  1024 kB
 ";
-        let (uid, rss_mb) = parse_proc_status(content);
+        let (uid, rss_mb, swap_mb) = parse_proc_status(content);
         assert_eq!(uid, Some(33));
         assert!((rss_mb - 4.0).abs() < 1e-9);
+        assert!((swap_mb - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn parse_smaps_rollup_derives_pss_uss_and_swap() {
+        let content = "\
+55ffe7953000-7ffd6d64e000 ---p 00000000 00:00 0                          [rollup]
+Rss:                4096 kB
+Pss:                2048 kB
+Shared_Clean:       2048 kB
+Private_Clean:       512 kB
+Private_Dirty:       512 kB
+Swap:               1024 kB
+SwapPss:            1024 kB
+";
+        let smaps = parse_smaps_rollup(content).unwrap();
+        assert_eq!(smaps.pss_mb, 2.0);
+        assert_eq!(smaps.uss_mb, 1.0);
+        assert_eq!(smaps.swap_mb, 1.0);
+        assert_eq!(parse_smaps_rollup("garbage"), None);
+    }
+
+    #[test]
+    fn memory_metric_falls_back_to_rss() {
+        let mut e = LsofEntry {
+            memory_mb: 8.0,
+            swap_mb: 0.5,
+            ..LsofEntry::test("1234", 80, 0)
+        };
+        // No smaps_rollup: PSS/USS show RSS, marked; swap has its own.
+        assert_eq!(e.memory(MemoryMetric::Pss), 8.0);
+        assert_eq!(e.get_memory_display(MemoryMetric::Uss), "~8.0MB");
+        assert_eq!(e.get_memory_display(MemoryMetric::Rss), "8.0MB");
+        assert_eq!(e.memory(MemoryMetric::Swap), 0.5);
+        assert_eq!(e.get_memory_display(MemoryMetric::Swap), "512.0KB");
+        e.smaps = Some(SmapsRollup {
+            pss_mb: 3.0,
+            uss_mb: 2.0,
+            swap_mb: 1.0,
+        });
+        assert_eq!(e.memory(MemoryMetric::Rss), 8.0);
+        assert_eq!(e.memory(MemoryMetric::Pss), 3.0);
+        assert_eq!(e.memory(MemoryMetric::Uss), 2.0);
+        assert_eq!(e.memory(MemoryMetric::Swap), 1.0);
     }
 
     #[test]
@@ -956,18 +2259,7 @@ www-data:x:33:33:www-data:/var/www:/usr/sbin/nologin
 
     #[test]
     fn lsof_entry_killable_check() {
-        let mut e = LsofEntry {
-            command: "x".into(),
-            pid: "1234".into(),
-            user: "u".into(),
-            local_addr: "*".into(),
-            port: 80,
-            protocol: "TCP",
-            memory_mb: 0.0,
-            start_time: None,
-            starttime_ticks: None,
-            cwd: None,
-        };
+        let mut e = LsofEntry::test("1234", 80, 0);
         assert!(e.is_killable());
         e.pid = "?".into();
         assert!(!e.is_killable());
@@ -1052,15 +2344,67 @@ www-data:x:33:33:www-data:/var/www:/usr/sbin/nologin
     }
 
     #[test]
-    fn kill_of_already_reaped_pid_is_terminated() {
+    fn kill_of_already_reaped_pid_sends_nothing() {
         let mut child = std::process::Command::new("true").spawn().unwrap();
         let pid = child.id().to_string();
         child.wait().unwrap();
         let outcome = kill_process_verified(&pid, Some(1)).unwrap();
         assert!(
-            matches!(outcome, KillOutcome::Terminated),
+            matches!(outcome, KillOutcome::AlreadyExited),
             "got: {:?}",
             outcome
         );
+        assert!(outcome.signals_sent().is_empty());
+    }
+
+    fn proc_info(pid: &str, ppid: u32, uid: u32, comm: &str, args: &str, ticks: u64) -> ProcInfo {
+        ProcInfo {
+            pid: pid.into(),
+            ppid: Some(ppid),
+            uid: Some(uid),
+            comm: comm.into(),
+            args: args.into(),
+            command: args.into(),
+            starttime_ticks: Some(ticks),
+        }
+    }
+
+    #[test]
+    fn mentions_port_only_as_a_whole_number() {
+        assert!(mentions_port("node server.js --port 3000", 3000));
+        assert!(mentions_port("python -m http.server -p3000", 3000));
+        assert!(mentions_port("app --bind=0.0.0.0:3000", 3000));
+        assert!(!mentions_port("app --bind=0.0.0.0:13000", 3000));
+        assert!(!mentions_port("postgres -D /var/lib/pg", 3000));
+    }
+
+    #[test]
+    fn ranks_candidates_of_the_sockets_user() {
+        let procs = vec![
+            proc_info("100", 1, 70, "postgres", "postgres -D /var/lib/pg", 10),
+            // A backend: folded into the postmaster above.
+            proc_info("101", 100, 70, "postgres", "postgres: checkpointer", 11),
+            proc_info("200", 1, 70, "bash", "bash", 5),
+            proc_info("300", 1, 70, "node", "node api.js --port 5432", 30),
+            // Launched it; doesn't hold it.
+            proc_info("299", 1, 70, "bash", "bash -c node api.js --port 5432", 29),
+            // Someone else's: never a candidate.
+            proc_info("400", 1, 0, "postgres", "postgres --port 5432", 1),
+        ];
+        let ranked = rank_candidates(70, 5432, &procs);
+        let pids: Vec<_> = ranked.iter().map(|c| c.pid.as_str()).collect();
+        assert_eq!(pids, ["300", "100"]);
+        assert_eq!(ranked[0].hint, "its command line mentions 5432");
+        assert_eq!(ranked[1].hint, "postgres usually listens on 5432");
+
+        // Nothing hints at it: no guess rather than a random one…
+        let idle = vec![
+            proc_info("500", 1, 71, "bash", "bash", 5),
+            proc_info("501", 1, 71, "vim", "vim notes.txt", 6),
+        ];
+        assert!(rank_candidates(71, 8080, &idle).is_empty());
+        // …unless it's the user's only process.
+        let lone = rank_candidates(71, 8080, &idle[..1]);
+        assert_eq!(lone[0].hint, "the user's only process");
     }
 }
@@ -1,9 +1,26 @@
 #[cfg(not(target_os = "linux"))]
 compile_error!("reaper currently only supports Linux (it reads /proc directly)");
 
+mod agent;
 mod app;
+mod audit;
+mod caps;
+mod cli;
+mod config;
+mod exposure;
+mod helper;
+mod history;
+mod launch;
 mod lsof;
+mod metrics;
+mod pick;
+mod protect;
+mod snapshot;
+mod sock_diag;
+mod stuck;
+mod timeline;
 mod ui;
+mod update;
 
 const HELP: &str = "\
 reaper — a linux tui for listing and killing listening ports
@@ -11,51 +28,95 @@ reaper — a linux tui for listing and killing listening ports
 Usage: reaper [OPTIONS | COMMAND]
 
 Commands:
-  update         Download and install the latest release
+  kill <PORT> [-y]   Kill what listens on PORT (asks first unless -y)
+  history            Show the kill audit log; filter with --port, --user,
+                     --grep TEXT, --since 12h, --last N; --json for raw records
+  update             Download and install the latest release; --from TARBALL
+                     [--sha256 FILE] installs a downloaded release offline
+                     (refused without a checksum unless --insecure)
+  agent              Scan continuously and serve listeners, open/close events
+                     and verified kills as JSON lines on a Unix socket
+                     ($XDG_RUNTIME_DIR/reaper/agent.sock, or --listen PATH
+                     [--group NAME])
+  serve-metrics --listen ADDR:PORT
+                     Serve listeners as OpenMetrics gauges (presence,
+                     memory, uptime, connections) at http://ADDR:PORT/metrics
+  snapshot [FILE]    Write the current listeners, with binary and cgroup, as
+                     JSON to FILE (or stdout)
+  diff BEFORE [AFTER]
+                     Show listeners added, removed or changed (owner,
+                     address, user, binary) between two snapshots, or a
+                     snapshot and now; exits 1 if anything differs
+  pick [--field pid|port|address|json]
+                     Choose a listener in the TUI (drawn on the terminal,
+                     not stdout) and print its pid, port, ip:port or JSON
+                     row, e.g. `kill -HUP $(reaper pick)`; exits 1 if
+                     cancelled
+  doctor             Show which capabilities reaper has and how to grant
+                     the few it needs instead of running it as root
+  helper             Privileged helper (run as root) that resolves and kills
+                     listeners for an unprivileged reaper; --listen PATH
+                     [--group NAME] serves a Unix socket instead of stdio
 
 Options:
-  -h, --help     Print this help
-  -V, --version  Print the version
+      --read-only    Observe only: no kills, restarts or closed connections
+                     (also `read_only = true` in config.toml)
+      --elevate[=sudo|pkexec]
+                     Start a root helper (sudo by default) so other users'
+                     listeners can be seen and killed without running the
+                     whole TUI as root
+      --helper-socket PATH
+                     Use a helper already running as `reaper helper --listen`
+      --inline[=HEIGHT]
+                     Draw a compact HEIGHT-line view (default 15) below
+                     the prompt instead of taking over the screen
+      --connect[=PATH]
+                     Show and kill through a running `reaper agent` instead
+                     of scanning locally
+  -h, --help         Print this help
+  -V, --version      Print the version
 
 Keys (inside the TUI):
-  ↑/↓ navigate • ⏎ kill (with confirmation) • / search
-  s or 1-7 sort • a show restricted • r refresh • q/Esc quit
+  ↑/↓ navigate • ⏎ kill (with confirmation; r there restarts it) • / search
+  s or 0-9 sort • a show restricted • r refresh • q/Esc quit
+  c connected peers of the selected listener (x there closes one)
+  e save the selected listener's launch as a script
+  m memory metric (RSS/PSS/USS/swap) • t event timeline
+  d merge IPv4/IPv6 sockets of one process into a TCP4/6 row
 
-Run with sudo to see and kill other users' listeners.
+Run with sudo, --elevate or --helper-socket to see and kill other users'
+listeners — or grant reaper just the capabilities it needs (`reaper doctor`).
+Processes matching $XDG_CONFIG_HOME/reaper/protected or /etc/reaper/protected
+(lines like `command sshd`, `user postgres`, `port 5432`, `unit nginx`) are
+marked ◆ and only killed after typing their port; pid 1 and reaper's own
+ancestors are never killed.
+Every kill is recorded in $XDG_STATE_HOME/reaper/kills.jsonl; set
+`syslog = true` in $XDG_CONFIG_HOME/reaper/config.toml to also log to syslog.
 Docs: https://reaper.aymenkrifa.com";
 
-/// Re-run the official installer, targeting the directory this binary
-/// runs from so the update lands in place regardless of where reaper was
-/// installed. The binary itself stays network-free: curl fetches, and the
-/// installer keeps sole ownership of checksum verification and messaging.
-fn self_update() -> color_eyre::Result<()> {
-    let exe = std::env::current_exe()?;
-    let Some(bin_dir) = exe.parent() else {
-        return Err(color_eyre::eyre::eyre!(
-            "could not determine where reaper is installed (running from {})",
-            exe.display()
-        ));
-    };
-    let status = std::process::Command::new("sh")
-        .args([
-            "-c",
-            "curl -LsSf https://reaper.aymenkrifa.com/install.sh | sh",
-        ])
-        .env("REAPER_BIN_DIR", bin_dir)
-        .status()?;
-    if !status.success() {
-        eprintln!(
-            "\nupdate failed — if reaper lives in a system directory, try: sudo reaper update"
-        );
-        std::process::exit(status.code().unwrap_or(1));
-    }
-    Ok(())
+/// `--inline` without a height.
+const INLINE_HEIGHT: u16 = 15;
+/// A title, three table rows and the status/help band.
+const MIN_INLINE_HEIGHT: u16 = 6;
+
+fn load_config() -> config::Config {
+    config::Config::load().unwrap_or_else(|e| {
+        eprintln!("reaper: {}", e);
+        std::process::exit(2);
+    })
+}
+
+fn usage(cli::UsageError(e): cli::UsageError) -> color_eyre::Result<()> {
+    eprintln!("{e}\n\n{HELP}");
+    std::process::exit(2);
 }
 
 fn main() -> color_eyre::Result<()> {
+    let args: Vec<String> = std::env::args().collect();
     // The installer parses `reaper --version` to report updates, so this
     // must work without a terminal and before any TUI setup.
-    if let Some(arg) = std::env::args().nth(1) {
+    if let Some(arg) = args.get(1) {
+        let rest = &args[2..];
         match arg.as_str() {
             "--version" | "-V" => {
                 println!("reaper {}", env!("CARGO_PKG_VERSION"));
@@ -65,17 +126,100 @@ fn main() -> color_eyre::Result<()> {
                 println!("{}", HELP);
                 return Ok(());
             }
-            "update" => return self_update(),
+            "update" => return update::run(rest).or_else(usage),
+            "history" => return cli::history(rest).or_else(usage),
+            "kill" => return cli::kill(rest, &load_config()).or_else(usage),
+            "helper" => return helper::run(rest).or_else(usage),
+            "doctor" => return cli::doctor().or_else(usage),
+            "agent" => return agent::run(rest, &load_config()).or_else(usage),
+            "serve-metrics" => return metrics::run(rest).or_else(usage),
+            "pick" => return pick::run(rest, &load_config()).or_else(usage),
+            "snapshot" => return snapshot::snapshot(rest).or_else(usage),
+            "diff" => return snapshot::diff(rest).or_else(usage),
+            _ => {}
+        }
+    }
+
+    let mut config = load_config();
+    let mut elevate = None;
+    let mut helper_socket = None;
+    let mut connect = None;
+    let mut inline = None;
+    let mut options = args[1..].iter();
+    while let Some(arg) = options.next() {
+        match arg.as_str() {
+            "--read-only" => config.read_only = true,
+            "--elevate" | "--elevate=sudo" => elevate = Some("sudo"),
+            "--elevate=pkexec" => elevate = Some("pkexec"),
+            "--connect" => connect = agent::default_socket(),
+            other if other.starts_with("--connect=") => {
+                connect = Some(std::path::PathBuf::from(&other["--connect=".len()..]));
+            }
+            "--inline" => inline = Some(INLINE_HEIGHT),
+            other if other.starts_with("--inline=") => {
+                let value = &other["--inline=".len()..];
+                match value.parse::<u16>() {
+                    Ok(height) if height >= MIN_INLINE_HEIGHT => inline = Some(height),
+                    _ => usage(cli::UsageError(format!(
+                        "--inline takes a height of at least {} lines, not {:?}",
+                        MIN_INLINE_HEIGHT, value
+                    )))?,
+                }
+            }
+            "--helper-socket" => match options.next() {
+                Some(path) => helper_socket = Some(path.clone()),
+                None => usage(cli::UsageError("--helper-socket needs a path".into()))?,
+            },
             other => {
                 eprintln!("unknown option: {other}\n\n{HELP}");
                 std::process::exit(2);
             }
         }
     }
-
+    // Before the TUI takes the screen: sudo and pkexec may need to ask
+    // for a password.
+    let helper = match (elevate, helper_socket) {
+        (Some(via), _) => Some(helper::Helper::spawn(via).map_err(|e| (via.to_string(), e))),
+        (None, Some(path)) => Some(helper::Helper::connect(&path).map_err(|e| (path, e))),
+        (None, None) => None,
+    }
+    .transpose()
+    .unwrap_or_else(|(what, e)| {
+        eprintln!(
+            "reaper: couldn't start the privileged helper ({}): {}",
+            what, e
+        );
+        std::process::exit(1);
+    });
+    let agent = connect.map(|path| {
+        agent::Client::connect(path.clone()).unwrap_or_else(|e| {
+            eprintln!(
+                "reaper: can't reach an agent at {} ({}) — is `reaper agent` running?",
+                path.display(),
+                e
+            );
+            std::process::exit(1);
+        })
+    });
     color_eyre::install()?;
-    let terminal = ratatui::init();
-    let result = app::App::new().run(terminal);
-    ratatui::restore();
+    let mut app = app::App::new(&config);
+    app.scanner.helper = helper;
+    app.scanner.agent = agent;
+    let Some(height) = inline else {
+        let mut terminal = ratatui::init();
+        let result = app.run(&mut terminal);
+        ratatui::restore();
+        return result;
+    };
+    let mut terminal = ratatui::init_with_options(ratatui::TerminalOptions {
+        viewport: ratatui::Viewport::Inline(height),
+    });
+    let result = app.run(&mut terminal);
+    // Wipe the view and leave the cursor where it started, like fzf
+    // --height; ratatui::restore would also leave an alternate screen
+    // that was never entered.
+    let _ = terminal.clear();
+    let _ = terminal.show_cursor();
+    let _ = ratatui::crossterm::terminal::disable_raw_mode();
     result
 }
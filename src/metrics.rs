@@ -0,0 +1,363 @@
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::cli::UsageError;
+use crate::lsof::{LsofEntry, Scanner};
+
+/// All a client gets to send its request in, however it trickles: the
+/// server is single-threaded, and a slow client would hold up the rest.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Request line and headers together; a GET needs nowhere near this.
+const MAX_REQUEST: u64 = 8192;
+
+/// Commands can run to kilobytes of flags; a label that long helps no
+/// one and bloats every scrape.
+const MAX_COMMAND_LABEL: usize = 200;
+
+const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Label value escaping per the exposition format: backslash, double
+/// quote and newline.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', r#"\""#)
+        .replace('\n', r"\n")
+}
+
+/// Identified by socket inode, not pid: SO_REUSEPORT servers share
+/// everything else, and two samples with one label set fail the whole
+/// scrape.
+fn labels(p: &LsofEntry) -> String {
+    let command: String = p.command.chars().take(MAX_COMMAND_LABEL).collect();
+    format!(
+        r#"port="{}",address="{}",protocol="{}",user="{}",command="{}",inode="{}""#,
+        p.port,
+        escape(&p.local_addr),
+        p.protocol,
+        escape(&p.user),
+        escape(&command),
+        p.inode
+    )
+}
+
+/// One metric family: its header, then a sample per listener that has a
+/// value for it.
+fn family(
+    out: &mut String,
+    name: &str,
+    unit: Option<&str>,
+    help: &str,
+    scan: &[LsofEntry],
+    value: impl Fn(&LsofEntry) -> Option<f64>,
+) {
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+    if let Some(unit) = unit {
+        let _ = writeln!(out, "# UNIT {} {}", name, unit);
+    }
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    for p in scan {
+        if let Some(v) = value(p) {
+            let _ = writeln!(out, "{}{{{}}} {}", name, labels(p), v);
+        }
+    }
+}
+
+/// The scan as an OpenMetrics exposition. Restricted rows only report
+/// presence: their memory and uptime are unknown, not zero.
+fn render(scan: &[LsofEntry], now: SystemTime) -> String {
+    const MB: f64 = 1024.0 * 1024.0;
+    let readable = |p: &LsofEntry| p.is_killable();
+    let mut out = String::new();
+    family(
+        &mut out,
+        "reaper_listener",
+        None,
+        "A listening socket, 1 while it exists.",
+        scan,
+        |_| Some(1.0),
+    );
+    family(
+        &mut out,
+        "reaper_listener_memory_rss_bytes",
+        Some("bytes"),
+        "Resident set size of the owning process.",
+        scan,
+        |p| readable(p).then_some(p.memory_mb * MB),
+    );
+    family(
+        &mut out,
+        "reaper_listener_memory_pss_bytes",
+        Some("bytes"),
+        "Proportional set size of the owning process, shared pages split among their sharers.",
+        scan,
+        |p| p.smaps.map(|s| s.pss_mb * MB),
+    );
+    family(
+        &mut out,
+        "reaper_listener_memory_uss_bytes",
+        Some("bytes"),
+        "Unique set size of the owning process: what killing it would free.",
+        scan,
+        |p| p.smaps.map(|s| s.uss_mb * MB),
+    );
+    family(
+        &mut out,
+        "reaper_listener_memory_swap_bytes",
+        Some("bytes"),
+        "Swap used by the owning process.",
+        scan,
+        |p| p.smaps.map(|s| s.swap_mb * MB),
+    );
+    family(
+        &mut out,
+        "reaper_listener_uptime_seconds",
+        Some("seconds"),
+        "Time since the owning process started.",
+        scan,
+        |p| {
+            p.start_time
+                .and_then(|t| now.duration_since(t).ok())
+                .map(|d| d.as_secs() as f64)
+        },
+    );
+    family(
+        &mut out,
+        "reaper_listener_connections",
+        None,
+        "Established, half-open and close-wait connections on the listener.",
+        scan,
+        |p| Some(p.connections.len() as f64),
+    );
+    family(
+        &mut out,
+        "reaper_listener_accept_queue",
+        None,
+        "Connections waiting in the listener's accept queue.",
+        scan,
+        |p| Some(p.backlog.queued as f64),
+    );
+    out.push_str("# EOF\n");
+    out
+}
+
+/// Reads from a stream until a fixed deadline, rather than allowing each
+/// read its own timeout.
+struct Deadline<'a> {
+    stream: &'a TcpStream,
+    until: Instant,
+}
+
+impl Read for Deadline<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let left = self.until.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+        self.stream.set_read_timeout(Some(left))?;
+        (&mut &*self.stream).read(buf)
+    }
+}
+
+/// Read the request line and skip the headers: nothing in them changes
+/// the answer. At most `MAX_REQUEST` bytes within `CLIENT_TIMEOUT`.
+fn read_request(stream: &TcpStream) -> io::Result<(String, String)> {
+    let deadline = Deadline {
+        stream,
+        until: Instant::now() + CLIENT_TIMEOUT,
+    };
+    let mut reader = BufReader::new(deadline.take(MAX_REQUEST));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && header.trim_end() != "" {
+        header.clear();
+    }
+    let mut parts = request_line.split_whitespace();
+    Ok((
+        parts.next().unwrap_or_default().to_string(),
+        parts.next().unwrap_or_default().to_string(),
+    ))
+}
+
+fn respond(mut stream: &TcpStream, status: &str, content_type: &str, body: &str) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )
+}
+
+fn handle(stream: &TcpStream, scanner: &mut Scanner) -> io::Result<()> {
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+    let text = "text/plain; charset=utf-8";
+    match read_request(stream)? {
+        (method, _) if method != "GET" => {
+            respond(stream, "405 Method Not Allowed", text, "GET only\n")
+        }
+        (_, path) if path == "/metrics" => respond(
+            stream,
+            "200 OK",
+            CONTENT_TYPE,
+            &render(&scanner.scan(), SystemTime::now()),
+        ),
+        (_, path) if path == "/" => respond(stream, "200 OK", text, "reaper: see /metrics\n"),
+        _ => respond(stream, "404 Not Found", text, "not found; try /metrics\n"),
+    }
+}
+
+/// `reaper serve-metrics --listen ADDR:PORT`: scan on every scrape and
+/// answer with OpenMetrics gauges. One scanner for the server's life, so
+/// inode resolution stays cached between scrapes.
+pub(crate) fn run(args: &[String]) -> Result<(), UsageError> {
+    let mut listen = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--listen" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| UsageError("--listen needs an address".to_string()))?;
+                listen = Some(
+                    value
+                        .parse::<SocketAddr>()
+                        .map_err(|_| UsageError(format!("not an address:port: {}", value)))?,
+                );
+            }
+            other => {
+                return Err(UsageError(format!(
+                    "unknown serve-metrics option: {}",
+                    other
+                )));
+            }
+        }
+    }
+    let Some(addr) = listen else {
+        return Err(UsageError(
+            "serve-metrics needs --listen ADDR:PORT, e.g. 127.0.0.1:9477".to_string(),
+        ));
+    };
+    let listener = TcpListener::bind(addr).unwrap_or_else(|e| {
+        eprintln!("reaper serve-metrics: can't listen on {}: {}", addr, e);
+        std::process::exit(1);
+    });
+    eprintln!("reaper serving metrics on http://{}/metrics", addr);
+    let mut scanner = Scanner::default();
+    for stream in listener.incoming().flatten() {
+        // One bad client is its own problem, not the server's.
+        let _ = handle(&stream, &mut scanner);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(pid: &str, port: u16, command: &str) -> LsofEntry {
+        LsofEntry {
+            command: command.into(),
+            user: "alice".into(),
+            local_addr: "127.0.0.1".into(),
+            ip: std::net::Ipv4Addr::LOCALHOST.into(),
+            memory_mb: 2.0,
+            start_time: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1000)),
+            ..LsofEntry::test(pid, port, 1)
+        }
+    }
+
+    #[test]
+    fn renders_gauges_with_escaped_labels() {
+        let mut restricted = entry("?", 22, "(restricted)");
+        restricted.start_time = None;
+        let out = render(
+            &[entry("42", 3000, "node \"dev\" c:\\srv"), restricted],
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1060),
+        );
+        let labels = r#"port="3000",address="127.0.0.1",protocol="TCP",user="alice",command="node \"dev\" c:\\srv",inode="1""#;
+        assert!(out.contains(&format!("reaper_listener{{{}}} 1\n", labels)));
+        assert!(out.contains(&format!(
+            "reaper_listener_memory_rss_bytes{{{}}} 2097152\n",
+            labels
+        )));
+        assert!(out.contains(&format!(
+            "reaper_listener_uptime_seconds{{{}}} 60\n",
+            labels
+        )));
+        assert!(out.contains("# UNIT reaper_listener_uptime_seconds seconds\n"));
+        // Present, but no made-up memory or uptime for the restricted row.
+        assert!(out.contains(r#"reaper_listener{port="22""#));
+        assert!(!out.contains(r#"reaper_listener_memory_rss_bytes{port="22""#));
+        assert!(!out.contains(r#"reaper_listener_uptime_seconds{port="22""#));
+        assert!(out.ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn reuseport_listeners_get_a_series_each() {
+        let first = entry("42", 3000, "nginx");
+        let second = LsofEntry {
+            inode: 2,
+            ..entry("43", 3000, "nginx")
+        };
+        let out = render(&[first, second], SystemTime::UNIX_EPOCH);
+        let presence: Vec<_> = out
+            .lines()
+            .filter(|l| l.starts_with("reaper_listener{"))
+            .collect();
+        assert_eq!(presence.len(), 2);
+        assert_ne!(presence[0], presence[1]);
+    }
+
+    #[test]
+    fn a_trickling_or_endless_request_is_cut_off() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut client = TcpStream::connect(addr).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        let writer = std::thread::spawn(move || {
+            // A header line with no end, faster than any per-read timeout.
+            let _ = client.write_all(b"GET /metrics HTTP/1.1\r\nX: ");
+            for _ in 0..(MAX_REQUEST / 64 + 2) {
+                if client.write_all(&[b'a'; 64]).is_err() {
+                    break;
+                }
+            }
+            client
+        });
+        let started = Instant::now();
+        let (method, path) = read_request(&server).unwrap();
+        assert_eq!((method.as_str(), path.as_str()), ("GET", "/metrics"));
+        assert!(started.elapsed() < CLIENT_TIMEOUT);
+        drop(writer.join());
+    }
+
+    #[test]
+    fn serves_metrics_over_http() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let mut scanner = Scanner::default();
+            for stream in listener.incoming().flatten().take(2) {
+                handle(&stream, &mut scanner).unwrap();
+            }
+        });
+        let get = |path: &str| {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            write!(stream, "GET {} HTTP/1.1\r\nHost: x\r\n\r\n", path).unwrap();
+            let mut response = String::new();
+            io::Read::read_to_string(&mut stream, &mut response).unwrap();
+            response
+        };
+        let metrics = get("/metrics");
+        assert!(metrics.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(metrics.contains(CONTENT_TYPE));
+        assert!(metrics.ends_with("# EOF\n"));
+        assert!(get("/nope").starts_with("HTTP/1.1 404"));
+    }
+}
@@ -0,0 +1,73 @@
+use std::fs;
+use std::io::{self, Write};
+
+use ratatui::Terminal;
+use ratatui::crossterm::{cursor, execute, terminal};
+use ratatui::prelude::CrosstermBackend;
+
+use crate::app::{App, PickField};
+use crate::cli::UsageError;
+use crate::config::Config;
+
+/// Where `reaper pick` draws: the controlling terminal, so stdout stays
+/// free for the answer. Stderr if there's no /dev/tty to open.
+fn tty() -> Box<dyn Write> {
+    match fs::OpenOptions::new().write(true).open("/dev/tty") {
+        Ok(tty) => Box::new(tty),
+        Err(_) => Box::new(io::stderr()),
+    }
+}
+
+fn restore_tty() {
+    let _ = terminal::disable_raw_mode();
+    let _ = execute!(tty(), terminal::LeaveAlternateScreen, cursor::Show);
+}
+
+/// `reaper pick [--field F]`: the TUI as a chooser. Enter prints the
+/// row's field to stdout and exits 0; quitting prints nothing and exits
+/// 1, like fzf. Nothing can be killed from a pick session.
+pub(crate) fn run(args: &[String], config: &Config) -> Result<(), UsageError> {
+    let mut field = PickField::Pid;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let name = match arg.as_str() {
+            "--field" => args.next().map(String::as_str).unwrap_or_default(),
+            other if other.starts_with("--field=") => &other["--field=".len()..],
+            other => return Err(UsageError(format!("unknown pick option: {}", other))),
+        };
+        field = PickField::parse(name).ok_or_else(|| {
+            UsageError(format!(
+                "--field takes pid, port, address or json, not {:?}",
+                name
+            ))
+        })?;
+    }
+    let mut app = App::new(config);
+    app.read_only = true;
+    app.picking = Some(field);
+
+    let result = (|| -> color_eyre::Result<()> {
+        let mut tty = tty();
+        terminal::enable_raw_mode()?;
+        execute!(tty, terminal::EnterAlternateScreen)?;
+        let hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            restore_tty();
+            hook(info);
+        }));
+        let mut terminal = Terminal::new(CrosstermBackend::new(tty))?;
+        app.run(&mut terminal)
+    })();
+    restore_tty();
+    if let Err(e) = result {
+        eprintln!("reaper pick: {}", e);
+        std::process::exit(1);
+    }
+    match app.picked {
+        Some(value) => {
+            println!("{}", value);
+            Ok(())
+        }
+        None => std::process::exit(1),
+    }
+}
@@ -0,0 +1,328 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::caps;
+use crate::config;
+use crate::lsof::{self, LsofEntry};
+
+/// System-wide rules, read in addition to the per-user file — on a shared
+/// server that's where "never casually kill sshd" belongs.
+const SYSTEM_RULES: &str = "/etc/reaper/protected";
+
+/// One line of a rules file.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Rule {
+    /// Executable name, as in /proc/<pid>/comm or the first word of the
+    /// command line.
+    Command(String),
+    User(String),
+    /// Inclusive port range; a single port is `(p, p)`.
+    Ports(u16, u16),
+    /// systemd unit, from the process's cgroup. `sshd` means `sshd.service`.
+    Unit(String),
+}
+
+impl Rule {
+    fn describe(&self) -> String {
+        match self {
+            Rule::Command(c) => format!("command {}", c),
+            Rule::User(u) => format!("user {}", u),
+            Rule::Ports(a, b) if a == b => format!("port {}", a),
+            Rule::Ports(a, b) => format!("ports {}-{}", a, b),
+            Rule::Unit(u) => format!("unit {}", u),
+        }
+    }
+}
+
+/// Protected-process rules from `$XDG_CONFIG_HOME/reaper/protected` and
+/// /etc/reaper/protected:
+///
+/// ```text
+/// # kind   value
+/// command  sshd
+/// user     postgres
+/// port     5432
+/// port     9000-9100
+/// unit     nginx
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Rules {
+    rules: Vec<Rule>,
+}
+
+fn parse_port_range(value: &str) -> Option<(u16, u16)> {
+    let (a, b) = value.split_once('-').unwrap_or((value, value));
+    let (a, b) = (a.trim().parse().ok()?, b.trim().parse().ok()?);
+    (a <= b).then_some((a, b))
+}
+
+fn parse(content: &str) -> Result<Vec<Rule>, String> {
+    let mut rules = Vec::new();
+    for (n, line) in content.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (kind, value) = line
+            .split_once(char::is_whitespace)
+            .map(|(k, v)| (k, v.trim()))
+            .ok_or_else(|| format!("line {}: expected `<kind> <value>`", n + 1))?;
+        rules.push(match kind {
+            "command" => Rule::Command(value.to_string()),
+            "user" => Rule::User(value.to_string()),
+            "port" => parse_port_range(value)
+                .map(|(a, b)| Rule::Ports(a, b))
+                .ok_or_else(|| format!("line {}: bad port or range `{}`", n + 1, value))?,
+            "unit" if value.contains('.') => Rule::Unit(value.to_string()),
+            "unit" => Rule::Unit(format!("{}.service", value)),
+            other => {
+                return Err(format!(
+                    "line {}: unknown rule `{}` (expected command, user, port or unit)",
+                    n + 1,
+                    other
+                ));
+            }
+        });
+    }
+    Ok(rules)
+}
+
+impl Rules {
+    /// Both files are optional; a malformed one is an error, same as
+    /// config.toml — a protection rule that silently doesn't apply is
+    /// worse than a refusal to start. A binary with file capabilities
+    /// goes by the system file alone.
+    pub(crate) fn load() -> Result<Rules, String> {
+        if caps::elevated_by_file() {
+            return Rules::system();
+        }
+        let mut paths = vec![PathBuf::from(SYSTEM_RULES)];
+        paths.extend(config::config_dir().map(|d| d.join("protected")));
+        Rules::read(paths)
+    }
+
+    /// Only /etc/reaper/protected — for the root helper, which mustn't
+    /// go by a rules file the asking user writes.
+    pub(crate) fn system() -> Result<Rules, String> {
+        Rules::read([PathBuf::from(SYSTEM_RULES)])
+    }
+
+    fn read(paths: impl IntoIterator<Item = PathBuf>) -> Result<Rules, String> {
+        let mut rules = Vec::new();
+        for path in paths {
+            match fs::read_to_string(&path) {
+                Ok(content) => {
+                    rules.extend(parse(&content).map_err(|e| format!("{}: {}", path.display(), e))?)
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(format!("{}: {}", path.display(), e)),
+            }
+        }
+        Ok(Rules { rules })
+    }
+}
+
+/// Why a row is protected.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Protection {
+    /// pid 1, reaper itself or one of its ancestors: killing it takes
+    /// down the system or the terminal reaper runs in. Never offered.
+    Pinned(&'static str),
+    /// Matched a rule: killable, but only after typing the port number.
+    Rule(String),
+}
+
+impl Protection {
+    pub(crate) fn reason(&self) -> String {
+        match self {
+            Protection::Pinned(why) => why.to_string(),
+            Protection::Rule(rule) => format!("protected by rule `{}`", rule),
+        }
+    }
+}
+
+/// `0::/system.slice/sshd.service` → `sshd.service`. The deepest unit
+/// wins, so a service inside a user session still names the service.
+fn parse_unit(cgroup: &str) -> Option<String> {
+    cgroup
+        .lines()
+        .filter_map(|l| l.splitn(3, ':').nth(2))
+        .flat_map(|path| path.split('/').rev())
+        .find(|c| c.ends_with(".service") || c.ends_with(".scope"))
+        .map(str::to_string)
+}
+
+fn parse_ppid(status: &str) -> Option<u32> {
+    status
+        .lines()
+        .find_map(|l| l.strip_prefix("PPid:"))
+        .and_then(|v| v.trim().parse().ok())
+}
+
+/// Decides which rows are protected: the configured rules plus the pinned
+/// processes (pid 1 and reaper's own ancestry), captured once at startup.
+#[derive(Debug, Default)]
+pub(crate) struct Guard {
+    rules: Rules,
+    /// (pid, starttime) of reaper and each ancestor up to init. Paired
+    /// with starttime so an ancestor that exits and has its PID recycled
+    /// doesn't leave the newcomer protected.
+    lineage: Vec<(String, Option<u64>)>,
+}
+
+impl Guard {
+    pub(crate) fn new(rules: Rules) -> Guard {
+        let mut lineage = Vec::new();
+        let mut pid = std::process::id();
+        while pid > 1 {
+            let pid_str = pid.to_string();
+            lineage.push((pid_str.clone(), lsof::starttime_ticks(&pid_str)));
+            let Some(parent) = fs::read_to_string(format!("/proc/{}/status", pid))
+                .ok()
+                .and_then(|s| parse_ppid(&s))
+            else {
+                break;
+            };
+            pid = parent;
+        }
+        Guard { rules, lineage }
+    }
+
+    pub(crate) fn check(&self, p: &LsofEntry) -> Option<Protection> {
+        if p.pid == "1" {
+            return Some(Protection::Pinned("pid 1 — the init system"));
+        }
+        if self
+            .lineage
+            .iter()
+            .any(|(pid, ticks)| *pid == p.pid && *ticks == p.starttime_ticks)
+        {
+            return Some(Protection::Pinned(
+                "reaper itself or a process it runs under — killing it would take reaper's terminal down",
+            ));
+        }
+        // Only read /proc for the kinds of rule actually configured.
+        let mut comm = None;
+        let mut unit = None;
+        for rule in &self.rules.rules {
+            let hit = match rule {
+                Rule::Command(c) => {
+                    let comm = comm.get_or_insert_with(|| {
+                        fs::read_to_string(format!("/proc/{}/comm", p.pid))
+                            .map(|c| c.trim_end().to_string())
+                            .unwrap_or_default()
+                    });
+                    // sshd and friends rewrite argv ("sshd: /usr/sbin/sshd
+                    // -D [listener]"), hence comm as well.
+                    *comm == *c
+                        || p.command
+                            .split_whitespace()
+                            .next()
+                            .map(|w| w.trim_end_matches(':'))
+                            == Some(c)
+                }
+                Rule::User(u) => p.user == *u,
+                Rule::Ports(a, b) => (*a..=*b).contains(&p.port),
+                Rule::Unit(u) => {
+                    unit.get_or_insert_with(|| {
+                        fs::read_to_string(format!("/proc/{}/cgroup", p.pid))
+                            .ok()
+                            .and_then(|c| parse_unit(&c))
+                    })
+                    .as_deref()
+                        == Some(u)
+                }
+            };
+            if hit {
+                return Some(Protection::Rule(rule.describe()));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(pid: &str, user: &str, port: u16, command: &str) -> LsofEntry {
+        LsofEntry {
+            command: command.into(),
+            user: user.into(),
+            ..LsofEntry::test(pid, port, 1)
+        }
+    }
+
+    #[test]
+    fn parses_rules_file() {
+        let rules = parse("# servers\ncommand sshd\nuser  postgres\nport 9000-9100 # metrics\nunit nginx\nunit user@1000.service\n").unwrap();
+        assert_eq!(
+            rules,
+            vec![
+                Rule::Command("sshd".into()),
+                Rule::User("postgres".into()),
+                Rule::Ports(9000, 9100),
+                Rule::Unit("nginx.service".into()),
+                Rule::Unit("user@1000.service".into()),
+            ]
+        );
+        assert!(parse("port 9100-9000").is_err());
+        assert!(parse("process sshd").unwrap_err().contains("unknown rule"));
+        assert!(parse("command").is_err());
+    }
+
+    #[test]
+    fn finds_the_unit_in_cgroup() {
+        assert_eq!(
+            parse_unit("0::/system.slice/sshd.service\n").as_deref(),
+            Some("sshd.service")
+        );
+        assert_eq!(
+            parse_unit("0::/user.slice/user-1000.slice/user@1000.service/app.slice/dev.service\n")
+                .as_deref(),
+            Some("dev.service")
+        );
+        assert_eq!(parse_unit("0::/\n"), None);
+    }
+
+    #[test]
+    fn pins_init_and_reapers_own_lineage() {
+        let guard = Guard::new(Rules::default());
+        assert!(matches!(
+            guard.check(&entry("1", "root", 22, "systemd")),
+            Some(Protection::Pinned(_))
+        ));
+        let me = std::process::id().to_string();
+        let mut own = entry(&me, "u", 8080, "reaper");
+        own.starttime_ticks = lsof::starttime_ticks(&me);
+        assert!(matches!(guard.check(&own), Some(Protection::Pinned(_))));
+        // Same pid, different incarnation: not ours.
+        own.starttime_ticks = own.starttime_ticks.map(|t| t + 1);
+        assert_eq!(guard.check(&own), None);
+    }
+
+    #[test]
+    fn rules_match_command_user_and_port() {
+        let guard = Guard {
+            rules: Rules {
+                rules: parse("command sshd\nuser postgres\nport 9000-9100").unwrap(),
+            },
+            lineage: Vec::new(),
+        };
+        // The pid doesn't exist, so only the command line can match.
+        let check = |user, port, command| guard.check(&entry("999999999", user, port, command));
+        assert_eq!(
+            check("root", 22, "sshd: /usr/sbin/sshd -D [listener]"),
+            Some(Protection::Rule("command sshd".into()))
+        );
+        assert_eq!(
+            check("postgres", 5432, "postgres -D /var/lib/pg"),
+            Some(Protection::Rule("user postgres".into()))
+        );
+        assert_eq!(
+            check("u", 9050, "node"),
+            Some(Protection::Rule("ports 9000-9100".into()))
+        );
+        assert_eq!(check("u", 3000, "node server.js"), None);
+    }
+}
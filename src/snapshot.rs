@@ -0,0 +1,323 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::cli::UsageError;
+use crate::lsof::{LsofEntry, Scanner};
+
+/// What `reaper snapshot` writes: the scan, plus what a diff needs that
+/// the table doesn't.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Snapshot {
+    /// The reaper version that took it.
+    pub(crate) reaper: String,
+    /// Unix seconds.
+    pub(crate) taken: u64,
+    pub(crate) listeners: Vec<Listener>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Listener {
+    #[serde(flatten)]
+    pub(crate) entry: LsofEntry,
+    /// /proc/<pid>/exe: which binary, even when argv[0] says otherwise.
+    /// Ends in " (deleted)" when the file was replaced under a running
+    /// process.
+    pub(crate) exe: Option<String>,
+    /// The process's cgroup path (the v2 one where there is one), e.g.
+    /// `/system.slice/nginx.service`.
+    pub(crate) cgroup: Option<String>,
+}
+
+fn parse_cgroup(cgroup: &str) -> Option<String> {
+    let lines = || cgroup.lines().filter_map(|l| l.splitn(3, ':').nth(2));
+    cgroup
+        .lines()
+        .find_map(|l| l.strip_prefix("0::"))
+        .or_else(|| lines().next())
+        .map(str::to_string)
+}
+
+impl Snapshot {
+    fn take(scanner: &mut Scanner) -> Snapshot {
+        let listeners = scanner
+            .scan()
+            .into_iter()
+            .map(|entry| {
+                let known = entry.is_killable();
+                let exe = known
+                    .then(|| fs::read_link(format!("/proc/{}/exe", entry.pid)).ok())
+                    .flatten()
+                    .map(|p| p.display().to_string());
+                let cgroup = known
+                    .then(|| fs::read_to_string(format!("/proc/{}/cgroup", entry.pid)).ok())
+                    .flatten()
+                    .and_then(|c| parse_cgroup(&c));
+                Listener { entry, exe, cgroup }
+            })
+            .collect();
+        Snapshot {
+            reaper: env!("CARGO_PKG_VERSION").to_string(),
+            taken: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            listeners,
+        }
+    }
+
+    fn load(path: &str) -> Result<Snapshot, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        serde_json::from_str(&text).map_err(|e| format!("{}: not a reaper snapshot: {}", path, e))
+    }
+}
+
+/// Everything listening on one port, as the diff sees it. Pids and
+/// start times are left out on purpose: a restart changes them without
+/// changing anything a deploy cares about.
+#[derive(Debug, Default, PartialEq)]
+struct PortState {
+    owners: BTreeSet<String>,
+    addresses: BTreeSet<String>,
+    users: BTreeSet<String>,
+    binaries: BTreeSet<String>,
+}
+
+fn by_port(listeners: &[Listener]) -> BTreeMap<u16, PortState> {
+    let mut ports: BTreeMap<u16, PortState> = BTreeMap::new();
+    for l in listeners {
+        let state = ports.entry(l.entry.port).or_default();
+        state.owners.insert(l.entry.command.clone());
+        state.addresses.insert(l.entry.ip.to_string());
+        state.users.insert(l.entry.user.clone());
+        state.binaries.extend(l.exe.clone());
+    }
+    ports
+}
+
+fn join(set: &BTreeSet<String>) -> String {
+    set.iter().cloned().collect::<Vec<_>>().join(", ")
+}
+
+#[derive(Debug, PartialEq)]
+enum Change {
+    Added(u16, PortState),
+    Removed(u16, PortState),
+    /// Field name, before, after.
+    Changed(u16, Vec<(&'static str, String, String)>),
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let describe = |f: &mut fmt::Formatter, sign, port, s: &PortState| {
+            write!(
+                f,
+                "{} :{}  {}  {}  {}",
+                sign,
+                port,
+                join(&s.addresses),
+                join(&s.users),
+                join(&s.owners)
+            )?;
+            if !s.binaries.is_empty() {
+                write!(f, "  ({})", join(&s.binaries))?;
+            }
+            Ok(())
+        };
+        match self {
+            Change::Added(port, s) => describe(f, "+", port, s),
+            Change::Removed(port, s) => describe(f, "-", port, s),
+            Change::Changed(port, fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(name, before, after)| format!("{} {} → {}", name, before, after))
+                    .collect();
+                write!(f, "~ :{}  {}", port, fields.join("; "))
+            }
+        }
+    }
+}
+
+fn compare(before: &[Listener], after: &[Listener]) -> Vec<Change> {
+    let mut before = by_port(before);
+    let after = by_port(after);
+    let mut changes = Vec::new();
+    for (port, now) in after {
+        let Some(was) = before.remove(&port) else {
+            changes.push(Change::Added(port, now));
+            continue;
+        };
+        let mut fields = Vec::new();
+        for (name, was, now) in [
+            ("owner", &was.owners, &now.owners),
+            ("address", &was.addresses, &now.addresses),
+            ("user", &was.users, &now.users),
+            ("binary", &was.binaries, &now.binaries),
+        ] {
+            if was != now {
+                fields.push((name, join(was), join(now)));
+            }
+        }
+        if !fields.is_empty() {
+            changes.push(Change::Changed(port, fields));
+        }
+    }
+    changes.extend(
+        before
+            .into_iter()
+            .map(|(port, was)| Change::Removed(port, was)),
+    );
+    changes.sort_by_key(|c| match c {
+        Change::Added(p, _) | Change::Removed(p, _) | Change::Changed(p, _) => *p,
+    });
+    changes
+}
+
+/// `reaper snapshot [FILE]`: the current scan as JSON, to FILE or stdout.
+pub(crate) fn snapshot(args: &[String]) -> Result<(), UsageError> {
+    let path = match args {
+        [] => None,
+        [path] if path == "-" => None,
+        [path] if !path.starts_with('-') => Some(path),
+        _ => return Err(UsageError("usage: reaper snapshot [FILE]".to_string())),
+    };
+    let snapshot = Snapshot::take(&mut Scanner::default());
+    let mut json = serde_json::to_string_pretty(&snapshot).expect("a scan serializes");
+    json.push('\n');
+    let result = match path {
+        Some(path) => fs::write(path, json).map_err(|e| format!("{}: {}", path, e)),
+        None => {
+            print!("{}", json);
+            Ok(())
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("reaper snapshot: {}", e);
+        std::process::exit(2);
+    }
+    Ok(())
+}
+
+/// `reaper diff BEFORE [AFTER]`: added, removed and changed listeners
+/// between two snapshots, or between one and what's listening now. Exits
+/// 1 when there are differences and 2 on trouble, like diff(1), so it can
+/// gate a deploy.
+pub(crate) fn diff(args: &[String]) -> Result<(), UsageError> {
+    if args.is_empty() || args.len() > 2 || args.iter().any(|a| a.starts_with('-')) {
+        return Err(UsageError(
+            "usage: reaper diff BEFORE.json [AFTER.json]".to_string(),
+        ));
+    }
+    let load = |path: &String| {
+        Snapshot::load(path).unwrap_or_else(|e| {
+            eprintln!("reaper diff: {}", e);
+            std::process::exit(2);
+        })
+    };
+    let before = load(&args[0]);
+    let after = match args.get(1) {
+        Some(path) => load(path),
+        None => Snapshot::take(&mut Scanner::default()),
+    };
+    let changes = compare(&before.listeners, &after.listeners);
+    for change in &changes {
+        println!("{}", change);
+    }
+    if !changes.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listener(pid: &str, port: u16, command: &str, exe: &str) -> Listener {
+        Listener {
+            entry: LsofEntry {
+                command: command.into(),
+                user: "www".into(),
+                local_addr: "127.0.0.1".into(),
+                ip: std::net::Ipv4Addr::LOCALHOST.into(),
+                ..LsofEntry::test(pid, port, port.into())
+            },
+            exe: Some(exe.into()),
+            cgroup: Some("/system.slice/app.service".into()),
+        }
+    }
+
+    #[test]
+    fn snapshots_round_trip_as_flat_json() {
+        let snapshot = Snapshot {
+            reaper: "1.0.0".into(),
+            taken: 1,
+            listeners: vec![listener("42", 8080, "app", "/usr/bin/app")],
+        };
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert!(json.contains(r#""port":8080,"#) && json.contains(r#""exe":"/usr/bin/app""#));
+        let back: Snapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.listeners[0].entry.protocol, "TCP");
+        assert_eq!(
+            back.listeners[0].cgroup.as_deref(),
+            Some("/system.slice/app.service")
+        );
+    }
+
+    #[test]
+    fn reads_the_unified_cgroup_first() {
+        assert_eq!(
+            parse_cgroup("12:pids:/user.slice\n0::/system.slice/nginx.service\n").as_deref(),
+            Some("/system.slice/nginx.service")
+        );
+        assert_eq!(
+            parse_cgroup("1:name=systemd:/system.slice/sshd.service\n").as_deref(),
+            Some("/system.slice/sshd.service")
+        );
+        assert_eq!(parse_cgroup(""), None);
+    }
+
+    #[test]
+    fn reports_what_changed_but_not_restarts() {
+        let before = [
+            listener("10", 3000, "node server.js", "/usr/bin/node"),
+            listener(
+                "20",
+                5432,
+                "postgres",
+                "/usr/lib/postgresql/15/bin/postgres",
+            ),
+            listener("30", 9000, "old", "/usr/bin/old"),
+        ];
+        let mut exposed = listener("11", 3000, "node server.js", "/usr/bin/node");
+        exposed.entry.ip = std::net::Ipv4Addr::UNSPECIFIED.into();
+        let after = [
+            exposed,
+            listener(
+                "21",
+                5432,
+                "postgres",
+                "/usr/lib/postgresql/16/bin/postgres",
+            ),
+            listener("40", 9100, "new", "/usr/bin/new"),
+        ];
+        let changes: Vec<String> = compare(&before, &after)
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                "~ :3000  address 127.0.0.1 → 0.0.0.0",
+                "~ :5432  binary /usr/lib/postgresql/15/bin/postgres → /usr/lib/postgresql/16/bin/postgres",
+                "- :9000  127.0.0.1  www  old  (/usr/bin/old)",
+                "+ :9100  127.0.0.1  www  new  (/usr/bin/new)",
+            ]
+        );
+        // Same listeners under new pids: nothing to report.
+        let restarted = [listener("99", 9000, "old", "/usr/bin/old")];
+        assert_eq!(compare(&before[2..], &restarted), []);
+    }
+}
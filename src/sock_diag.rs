@@ -0,0 +1,422 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+/// TCP_LISTEN in the kernel's state numbering (the 0A in /proc/net/tcp).
+const TCP_LISTEN: u32 = 10;
+const SOCK_DIAG_BY_FAMILY: u16 = 20;
+const SOCK_DESTROY: u16 = 21;
+/// Cookie value meaning "match on the 4-tuple alone".
+const INET_DIAG_NOCOOKIE: u32 = !0;
+
+// The inet_diag wire structs from <linux/inet_diag.h>. libc has the
+// netlink basics but not these.
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct InetDiagSockId {
+    sport: u16,
+    dport: u16,
+    src: [u32; 4],
+    dst: [u32; 4],
+    interface: u32,
+    cookie: [u32; 2],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct InetDiagReqV2 {
+    family: u8,
+    protocol: u8,
+    ext: u8,
+    pad: u8,
+    states: u32,
+    id: InetDiagSockId,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct InetDiagMsg {
+    family: u8,
+    state: u8,
+    timer: u8,
+    retrans: u8,
+    id: InetDiagSockId,
+    expires: u32,
+    rqueue: u32,
+    wqueue: u32,
+    uid: u32,
+    inode: u32,
+}
+
+#[repr(C)]
+struct Request {
+    header: libc::nlmsghdr,
+    body: InetDiagReqV2,
+}
+
+/// Accept queue length and `listen()` backlog of one listening socket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListenQueue {
+    pub queued: u32,
+    pub limit: u32,
+}
+
+/// A NETLINK_SOCK_DIAG socket. Unprivileged for dumps — any user can
+/// list sockets here, just as any user can read /proc/net/tcp.
+struct DiagSocket(OwnedFd);
+
+impl DiagSocket {
+    fn open() -> io::Result<DiagSocket> {
+        let fd = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+                libc::NETLINK_SOCK_DIAG,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(DiagSocket(unsafe { OwnedFd::from_raw_fd(fd) }))
+    }
+
+    fn send(&self, flags: u16, body: InetDiagReqV2) -> io::Result<()> {
+        self.send_as(SOCK_DIAG_BY_FAMILY, flags, body)
+    }
+
+    fn send_as(&self, kind: u16, flags: u16, body: InetDiagReqV2) -> io::Result<()> {
+        let request = Request {
+            header: libc::nlmsghdr {
+                nlmsg_len: size_of::<Request>() as u32,
+                nlmsg_type: kind,
+                nlmsg_flags: flags,
+                nlmsg_seq: 1,
+                nlmsg_pid: 0,
+            },
+            body,
+        };
+        let sent = unsafe {
+            libc::send(
+                self.0.as_raw_fd(),
+                (&raw const request).cast(),
+                size_of::<Request>(),
+                0,
+            )
+        };
+        if sent < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Read replies, handing each SOCK_DIAG_BY_FAMILY payload to
+    /// `on_msg`. A dump runs until the kernel says it's done; any other
+    /// request is answered in a single datagram.
+    fn receive(&self, dump: bool, mut on_msg: impl FnMut(&InetDiagMsg)) -> io::Result<()> {
+        let mut buf = vec![0u8; 32 * 1024];
+        loop {
+            let n =
+                unsafe { libc::recv(self.0.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len(), 0) };
+            if n < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if parse_messages(&buf[..n as usize], &mut on_msg)? || !dump {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Walk one recv()'s worth of netlink messages. `Ok(true)` once the
+/// terminating NLMSG_DONE has been seen.
+fn parse_messages(mut buf: &[u8], on_msg: &mut impl FnMut(&InetDiagMsg)) -> io::Result<bool> {
+    const HEADER: usize = size_of::<libc::nlmsghdr>();
+    while buf.len() >= HEADER {
+        let header: libc::nlmsghdr = unsafe { std::ptr::read_unaligned(buf.as_ptr().cast()) };
+        let len = header.nlmsg_len as usize;
+        if len < HEADER || len > buf.len() {
+            break;
+        }
+        let payload = &buf[HEADER..len];
+        match i32::from(header.nlmsg_type) {
+            libc::NLMSG_DONE => return Ok(true),
+            libc::NLMSG_ERROR => {
+                if payload.len() >= size_of::<i32>() {
+                    let errno = -i32::from_ne_bytes(payload[..4].try_into().unwrap());
+                    if errno != 0 {
+                        return Err(io::Error::from_raw_os_error(errno));
+                    }
+                }
+                // An error of 0 is an ACK — the end of a non-dump request.
+                return Ok(true);
+            }
+            _ if payload.len() >= size_of::<InetDiagMsg>() => {
+                on_msg(&unsafe { std::ptr::read_unaligned(payload.as_ptr().cast()) });
+            }
+            _ => {}
+        }
+        // Messages are padded to 4-byte boundaries.
+        let advance = (len + 3) & !3;
+        buf = buf.get(advance..).unwrap_or_default();
+    }
+    Ok(false)
+}
+
+/// Accept queues of every TCP listener, by socket inode. /proc/net/tcp
+/// only carries the current queue length — the backlog limit it would
+/// be measured against is only exported over sock_diag (it's what
+/// `ss -lnt` shows as Send-Q).
+pub fn listen_queues() -> io::Result<HashMap<u64, ListenQueue>> {
+    let socket = DiagSocket::open()?;
+    let mut out = HashMap::new();
+    for family in [libc::AF_INET, libc::AF_INET6] {
+        socket.send(
+            (libc::NLM_F_REQUEST | libc::NLM_F_DUMP) as u16,
+            InetDiagReqV2 {
+                family: family as u8,
+                protocol: libc::IPPROTO_TCP as u8,
+                states: 1 << TCP_LISTEN,
+                ..Default::default()
+            },
+        )?;
+        socket.receive(true, |msg| {
+            out.insert(
+                u64::from(msg.inode),
+                ListenQueue {
+                    queued: msg.rqueue,
+                    limit: msg.wqueue,
+                },
+            );
+        })?;
+    }
+    Ok(out)
+}
+
+/// Why a connection couldn't be closed, worded for the status line.
+#[derive(Debug)]
+pub enum DestroyError {
+    /// Nothing holds that 4-tuple any more — it closed on its own.
+    Gone,
+    /// The 4-tuple is held by a different socket than the one on screen
+    /// (the client reconnected from the same port). Not touched.
+    Reused,
+    /// Kernel built without CONFIG_INET_DIAG_DESTROY.
+    Unsupported,
+    /// SOCK_DESTROY needs CAP_NET_ADMIN.
+    PermissionDenied,
+    Io(io::Error),
+}
+
+impl fmt::Display for DestroyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DestroyError::Gone => write!(f, "connection already closed"),
+            DestroyError::Reused => {
+                write!(
+                    f,
+                    "that address pair now belongs to a new connection — left alone"
+                )
+            }
+            DestroyError::Unsupported => write!(
+                f,
+                "this kernel can't close sockets (built without CONFIG_INET_DIAG_DESTROY)"
+            ),
+            DestroyError::PermissionDenied => {
+                write!(
+                    f,
+                    "closing a socket needs CAP_NET_ADMIN — sudo, or see `reaper doctor`"
+                )
+            }
+            DestroyError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<io::Error> for DestroyError {
+    fn from(e: io::Error) -> DestroyError {
+        match e.raw_os_error() {
+            Some(libc::ENOENT) => DestroyError::Gone,
+            Some(libc::EOPNOTSUPP) => DestroyError::Unsupported,
+            Some(libc::EPERM | libc::EACCES) => DestroyError::PermissionDenied,
+            _ => DestroyError::Io(e),
+        }
+    }
+}
+
+fn addr_words(ip: IpAddr) -> [u32; 4] {
+    // Network byte order, as the kernel stores them: reinterpret the
+    // octets rather than converting.
+    match ip {
+        IpAddr::V4(v4) => [u32::from_ne_bytes(v4.octets()), 0, 0, 0],
+        IpAddr::V6(v6) => {
+            let o = v6.octets();
+            std::array::from_fn(|i| u32::from_ne_bytes(o[i * 4..i * 4 + 4].try_into().unwrap()))
+        }
+    }
+}
+
+fn exact_request(local: SocketAddr, remote: SocketAddr) -> InetDiagReqV2 {
+    InetDiagReqV2 {
+        family: if local.is_ipv4() {
+            libc::AF_INET as u8
+        } else {
+            libc::AF_INET6 as u8
+        },
+        protocol: libc::IPPROTO_TCP as u8,
+        states: !0,
+        id: InetDiagSockId {
+            sport: local.port().to_be(),
+            dport: remote.port().to_be(),
+            src: addr_words(local.ip()),
+            dst: addr_words(remote.ip()),
+            interface: 0,
+            cookie: [INET_DIAG_NOCOOKIE; 2],
+        },
+        ..Default::default()
+    }
+}
+
+/// Close one TCP connection, as `ss -K` does: the kernel aborts it (the
+/// peer gets a RST, the owning process an ECONNABORTED) and the server
+/// process carries on.
+///
+/// The 4-tuple is looked up first and must still belong to socket
+/// `inode`; the destroy then names the socket by its cookie as well, so
+/// it can't land on a connection that replaced it in between.
+pub fn destroy(local: SocketAddr, remote: SocketAddr, inode: u64) -> Result<(), DestroyError> {
+    let socket = DiagSocket::open()?;
+    let request = exact_request(local, remote);
+
+    socket.send(libc::NLM_F_REQUEST as u16, request)?;
+    let mut found = None;
+    socket.receive(false, |msg| found = Some(*msg))?;
+    let Some(found) = found else {
+        return Err(DestroyError::Gone);
+    };
+    // The kernel's lookup falls back to the listener when no connection
+    // holds the 4-tuple; an inode of 0 is one orphaned by its process
+    // (TIME_WAIT). Either way, the connection on screen is closed.
+    if u32::from(found.state) == TCP_LISTEN || found.inode == 0 {
+        return Err(DestroyError::Gone);
+    }
+    if u64::from(found.inode) != inode {
+        return Err(DestroyError::Reused);
+    }
+
+    let mut destroy = request;
+    destroy.id.cookie = found.id.cookie;
+    socket.send_as(
+        SOCK_DESTROY,
+        (libc::NLM_F_REQUEST | libc::NLM_F_ACK) as u16,
+        destroy,
+    )?;
+    socket.receive(false, |_| {})?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(kind: u16, payload: &[u8]) -> Vec<u8> {
+        let header = libc::nlmsghdr {
+            nlmsg_len: (size_of::<libc::nlmsghdr>() + payload.len()) as u32,
+            nlmsg_type: kind,
+            nlmsg_flags: 0,
+            nlmsg_seq: 1,
+            nlmsg_pid: 0,
+        };
+        let mut out = unsafe {
+            std::slice::from_raw_parts(
+                (&raw const header).cast::<u8>(),
+                size_of::<libc::nlmsghdr>(),
+            )
+        }
+        .to_vec();
+        out.extend_from_slice(payload);
+        out
+    }
+
+    fn diag_msg(inode: u32, rqueue: u32, wqueue: u32) -> Vec<u8> {
+        let msg = InetDiagMsg {
+            family: libc::AF_INET as u8,
+            state: TCP_LISTEN as u8,
+            timer: 0,
+            retrans: 0,
+            id: InetDiagSockId::default(),
+            expires: 0,
+            rqueue,
+            wqueue,
+            uid: 1000,
+            inode,
+        };
+        message(SOCK_DIAG_BY_FAMILY, unsafe {
+            std::slice::from_raw_parts((&raw const msg).cast(), size_of::<InetDiagMsg>())
+        })
+    }
+
+    #[test]
+    fn parses_a_dump_until_done() {
+        let mut buf = diag_msg(318513, 12, 128);
+        buf.extend(diag_msg(24707, 0, 4096));
+        let mut seen = Vec::new();
+        let done = parse_messages(&buf, &mut |m: &InetDiagMsg| {
+            seen.push((m.inode, m.rqueue, m.wqueue))
+        })
+        .unwrap();
+        assert!(!done);
+        assert_eq!(seen, vec![(318513, 12, 128), (24707, 0, 4096)]);
+
+        let done = parse_messages(&message(libc::NLMSG_DONE as u16, &[0; 4]), &mut |_| {
+            panic!("DONE carries no socket")
+        })
+        .unwrap();
+        assert!(done);
+    }
+
+    #[test]
+    fn netlink_errors_become_io_errors() {
+        let err = parse_messages(
+            &message(libc::NLMSG_ERROR as u16, &(-libc::EPERM).to_ne_bytes()),
+            &mut |_| {},
+        )
+        .unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::EPERM));
+    }
+
+    #[test]
+    fn errno_maps_to_an_explanation() {
+        let from = |errno| DestroyError::from(io::Error::from_raw_os_error(errno));
+        assert!(matches!(from(libc::EOPNOTSUPP), DestroyError::Unsupported));
+        assert!(matches!(from(libc::EPERM), DestroyError::PermissionDenied));
+        assert!(matches!(from(libc::ENOENT), DestroyError::Gone));
+        assert!(
+            from(libc::EOPNOTSUPP)
+                .to_string()
+                .contains("CONFIG_INET_DIAG_DESTROY")
+        );
+        assert!(from(libc::EPERM).to_string().contains("CAP_NET_ADMIN"));
+    }
+
+    #[test]
+    fn exact_request_uses_network_order() {
+        let req = exact_request(
+            "127.0.0.1:8080".parse().unwrap(),
+            "10.0.0.9:51234".parse().unwrap(),
+        );
+        assert_eq!(req.family, libc::AF_INET as u8);
+        assert_eq!(req.id.sport.to_ne_bytes(), 8080u16.to_be_bytes());
+        assert_eq!(req.id.src[0].to_ne_bytes(), [127, 0, 0, 1]);
+        assert_eq!(req.id.dst[0].to_ne_bytes(), [10, 0, 0, 9]);
+
+        let req = exact_request(
+            "[::ffff:127.0.0.1]:8080".parse().unwrap(),
+            "[::1]:51234".parse().unwrap(),
+        );
+        assert_eq!(req.family, libc::AF_INET6 as u8);
+        assert_eq!(req.id.src[3].to_ne_bytes(), [127, 0, 0, 1]);
+        assert_eq!(req.id.dst[3].to_ne_bytes(), [0, 0, 0, 1]);
+    }
+}
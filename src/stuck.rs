@@ -0,0 +1,206 @@
+use std::fs;
+
+use crate::lsof;
+
+/// Kernel stack frames shown in the panel. The interesting ones — the
+/// wait the task is blocked in and what called it — are at the top.
+const STACK_FRAMES: usize = 4;
+
+/// Why a process is still around after SIGKILL, gathered from /proc so
+/// the user can tell "wait", "deal with the parent" and "reboot" apart.
+#[derive(Debug, Clone)]
+pub(crate) struct StuckDiagnostics {
+    pub(crate) pid: String,
+    pub(crate) command: String,
+    starttime_ticks: Option<u64>,
+    /// `None` once the process has finally gone.
+    pub(crate) state: Option<char>,
+    /// Kernel function the task is sleeping in.
+    pub(crate) wchan: Option<String>,
+    /// Top kernel stack frames. `None` when unreadable — /proc/<pid>/stack
+    /// needs root.
+    pub(crate) stack: Option<Vec<String>>,
+    /// (pid, comm) of the ptrace tracer, if one is attached.
+    pub(crate) tracer: Option<(u32, String)>,
+    /// (pid, comm) of the parent — the one that has to reap a zombie.
+    pub(crate) parent: Option<(u32, String)>,
+}
+
+/// What the diagnostics add up to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Verdict {
+    /// It did die after all.
+    Exited,
+    /// Dead, but its parent hasn't called wait() on it.
+    Zombie,
+    /// Blocked in the kernel (state D), typically on I/O: NFS, FUSE, a
+    /// dying disk. Signals are only delivered when the call returns.
+    Uninterruptible,
+    /// A debugger or tracer is attached and holding it.
+    Traced,
+    /// Nothing conclusive — SIGKILL may simply still be in flight.
+    Unclear,
+}
+
+impl Verdict {
+    pub(crate) fn advice(self) -> &'static str {
+        match self {
+            Verdict::Exited => "it has exited now — nothing left to do",
+            Verdict::Zombie => {
+                "already dead; its parent hasn't reaped it. Kill or restart the parent to clear it"
+            }
+            Verdict::Uninterruptible => {
+                "blocked in the kernel, usually on I/O (NFS, FUSE, a failing disk). It dies when that call returns — wait, fix the storage, or reboot if it never does"
+            }
+            Verdict::Traced => "held by a debugger. Detach or kill the tracer, then retry",
+            Verdict::Unclear => {
+                "no obvious cause — the kill may still be in flight. Refresh (r) in a moment"
+            }
+        }
+    }
+}
+
+fn parse_status_ids(content: &str) -> (Option<u32>, Option<u32>) {
+    let field = |key: &str| {
+        content
+            .lines()
+            .find_map(|l| l.strip_prefix(key))
+            .and_then(|v| v.trim().parse::<u32>().ok())
+    };
+    (field("TracerPid:"), field("PPid:"))
+}
+
+/// `[<0>] rpc_wait_bit_killable+0x24/0xa0` → `rpc_wait_bit_killable`.
+fn parse_stack(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter_map(|l| {
+            let frame = l.split_once("] ").map_or(l, |(_, f)| f);
+            let name = frame.split('+').next()?.trim();
+            (!name.is_empty()).then(|| name.to_string())
+        })
+        .take(STACK_FRAMES)
+        .collect()
+}
+
+fn comm(pid: u32) -> String {
+    fs::read_to_string(format!("/proc/{}/comm", pid))
+        .map(|c| c.trim_end().to_string())
+        .unwrap_or_else(|_| "?".to_string())
+}
+
+impl StuckDiagnostics {
+    pub(crate) fn gather(
+        pid: &str,
+        command: &str,
+        starttime_ticks: Option<u64>,
+    ) -> StuckDiagnostics {
+        let state = lsof::process_state(pid, starttime_ticks);
+        // Once it's gone (or the PID was recycled) nothing under
+        // /proc/<pid> is about this process any more.
+        let read = |file: &str| {
+            state.and_then(|_| fs::read_to_string(format!("/proc/{}/{}", pid, file)).ok())
+        };
+        let (tracer, ppid) = read("status")
+            .map(|s| parse_status_ids(&s))
+            .unwrap_or_default();
+        StuckDiagnostics {
+            pid: pid.to_string(),
+            command: command.to_string(),
+            starttime_ticks,
+            state,
+            // "0" means not sleeping in any particular function.
+            wchan: read("wchan").filter(|w| !w.is_empty() && w != "0"),
+            stack: read("stack").map(|s| parse_stack(&s)),
+            tracer: tracer.filter(|&t| t != 0).map(|t| (t, comm(t))),
+            parent: ppid.filter(|&p| p != 0).map(|p| (p, comm(p))),
+        }
+    }
+
+    /// Re-read everything: a D-state process may come unstuck, a zombie
+    /// may get reaped.
+    pub(crate) fn refresh(&mut self) {
+        *self = StuckDiagnostics::gather(&self.pid, &self.command, self.starttime_ticks);
+    }
+
+    pub(crate) fn verdict(&self) -> Verdict {
+        match self.state {
+            None | Some('X') => Verdict::Exited,
+            Some('Z') => Verdict::Zombie,
+            Some('D') => Verdict::Uninterruptible,
+            _ if self.tracer.is_some() => Verdict::Traced,
+            Some('t') => Verdict::Traced,
+            _ => Verdict::Unclear,
+        }
+    }
+
+    /// The state letter spelled out.
+    pub(crate) fn state_label(&self) -> &'static str {
+        match self.state {
+            Some('R') => "running",
+            Some('S') => "sleeping",
+            Some('D') => "uninterruptible sleep",
+            Some('Z') => "zombie",
+            Some('T') => "stopped",
+            Some('t') => "tracing stop",
+            Some('X') | None => "gone",
+            Some(_) => "other",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diag(state: Option<char>, tracer: Option<(u32, String)>) -> StuckDiagnostics {
+        StuckDiagnostics {
+            pid: "10".into(),
+            command: "server".into(),
+            starttime_ticks: Some(1),
+            state,
+            wchan: None,
+            stack: None,
+            tracer,
+            parent: Some((1, "systemd".into())),
+        }
+    }
+
+    #[test]
+    fn parses_tracer_and_parent_from_status() {
+        let status = "Name:\tserver\nState:\tD (disk sleep)\nTgid:\t10\nPid:\t10\nPPid:\t1\nTracerPid:\t4242\n";
+        assert_eq!(parse_status_ids(status), (Some(4242), Some(1)));
+    }
+
+    #[test]
+    fn parses_symbol_names_from_kernel_stack() {
+        let stack = "\
+[<0>] rpc_wait_bit_killable+0x24/0xa0
+[<0>] __rpc_execute+0x131/0x3f0
+[<0>] rpc_execute+0x5b/0xa0
+[<0>] rpc_run_task+0x13d/0x180
+[<0>] nfs4_call_sync_sequence+0x64/0xa0
+";
+        assert_eq!(
+            parse_stack(stack),
+            vec![
+                "rpc_wait_bit_killable",
+                "__rpc_execute",
+                "rpc_execute",
+                "rpc_run_task"
+            ]
+        );
+    }
+
+    #[test]
+    fn verdict_follows_state_then_tracer() {
+        assert_eq!(diag(None, None).verdict(), Verdict::Exited);
+        assert_eq!(diag(Some('Z'), None).verdict(), Verdict::Zombie);
+        assert_eq!(diag(Some('D'), None).verdict(), Verdict::Uninterruptible);
+        assert_eq!(
+            diag(Some('S'), Some((7, "gdb".into()))).verdict(),
+            Verdict::Traced
+        );
+        assert_eq!(diag(Some('S'), None).verdict(), Verdict::Unclear);
+    }
+}
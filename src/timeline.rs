@@ -0,0 +1,278 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::lsof::{EntryId, LsofEntry};
+
+/// How many events the timeline pane keeps. A noisy build can open and
+/// close dozens of ports a minute; the pane only shows the newest few
+/// anyway, and nothing older is worth the memory.
+const CAPACITY: usize = 200;
+
+/// How long a newly appeared row stays highlighted, and how long a removed
+/// one lingers as a ghost at the bottom of the table. Long enough to catch
+/// the eye across a couple of refreshes, short enough not to clutter.
+const HIGHLIGHT_FOR: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone)]
+pub(crate) enum EventKind {
+    Opened,
+    Closed,
+    /// The same address/port is now held by a different process — a
+    /// restart, a supervisor respawn, or a port handed to a child.
+    OwnerChanged {
+        previous: Box<LsofEntry>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Event {
+    pub(crate) at: SystemTime,
+    pub(crate) kind: EventKind,
+    /// The listener after the change — or, for `Closed`, the one that went
+    /// away.
+    pub(crate) entry: LsofEntry,
+}
+
+/// What makes two scans' rows "the same listener" for diffing: where it
+/// listens, not who holds it — so an owner change is reported as such
+/// instead of as a close plus an open. SO_REUSEPORT servers put several
+/// rows under one key.
+type SocketKey = (&'static str, String, u16);
+
+fn socket_key(p: &LsofEntry) -> SocketKey {
+    (p.protocol, p.local_addr.clone(), p.port)
+}
+
+fn same_owner(a: &LsofEntry, b: &LsofEntry) -> bool {
+    a.pid == b.pid && a.starttime_ticks == b.starttime_ticks && a.user == b.user
+}
+
+fn by_key(scan: &[LsofEntry]) -> HashMap<SocketKey, Vec<&LsofEntry>> {
+    let mut keyed: HashMap<SocketKey, Vec<&LsofEntry>> = HashMap::new();
+    for p in scan {
+        keyed.entry(socket_key(p)).or_default().push(p);
+    }
+    keyed
+}
+
+/// Changes between two consecutive scans, in the order they should be
+/// listed: closes first, then owner changes, then opens. Under one key,
+/// owners present in both scans are unchanged; of the rest, a departed
+/// owner paired with an arrived one is an owner change, and any left
+/// over closed or opened.
+pub(crate) fn diff(old: &[LsofEntry], new: &[LsofEntry]) -> Vec<(EventKind, LsofEntry)> {
+    let old_by_key = by_key(old);
+    let new_by_key = by_key(new);
+    let mut seen = HashSet::new();
+    let keys = old
+        .iter()
+        .chain(new)
+        .map(socket_key)
+        .filter(|k| seen.insert(k.clone()));
+
+    let (mut closed, mut changed, mut opened) = (Vec::new(), Vec::new(), Vec::new());
+    for key in keys {
+        let was = old_by_key.get(&key).map_or(&[][..], Vec::as_slice);
+        let now = new_by_key.get(&key).map_or(&[][..], Vec::as_slice);
+        let gone: Vec<&LsofEntry> = was
+            .iter()
+            .copied()
+            .filter(|p| !now.iter().any(|n| same_owner(p, n)))
+            .collect();
+        let came: Vec<&LsofEntry> = now
+            .iter()
+            .copied()
+            .filter(|n| !was.iter().any(|p| same_owner(p, n)))
+            .collect();
+        for (prev, p) in gone.iter().zip(&came) {
+            changed.push((
+                EventKind::OwnerChanged {
+                    previous: Box::new((*prev).clone()),
+                },
+                (*p).clone(),
+            ));
+        }
+        closed.extend(
+            gone.iter()
+                .skip(came.len())
+                .map(|p| (EventKind::Closed, (*p).clone())),
+        );
+        opened.extend(
+            came.iter()
+                .skip(gone.len())
+                .map(|p| (EventKind::Opened, (*p).clone())),
+        );
+    }
+    closed.extend(changed);
+    closed.extend(opened);
+    closed
+}
+
+/// Bounded history of listeners appearing and disappearing between
+/// scans. With a 1s refresh, a port that lives for a few seconds during a
+/// build is otherwise invisible unless you happen to be looking.
+#[derive(Debug, Default)]
+pub(crate) struct Timeline {
+    events: VecDeque<Event>,
+    /// `None` until the first scan: the initial listing is the baseline,
+    /// not a burst of "opened" events.
+    previous: Option<Vec<LsofEntry>>,
+    fresh: HashMap<EntryId, Instant>,
+    /// Each with how many rows its address still had when it closed.
+    ghosts: Vec<(LsofEntry, Instant, usize)>,
+}
+
+impl Timeline {
+    pub(crate) fn observe(&mut self, scan: &[LsofEntry]) {
+        let now = Instant::now();
+        let at = SystemTime::now();
+        if let Some(previous) = &self.previous {
+            for (kind, entry) in diff(previous, scan) {
+                match kind {
+                    EventKind::Closed => {
+                        self.fresh.remove(&entry.id());
+                        let siblings = scan
+                            .iter()
+                            .filter(|p| socket_key(p) == socket_key(&entry))
+                            .count();
+                        self.ghosts.push((entry.clone(), now, siblings));
+                    }
+                    _ => {
+                        self.fresh.insert(entry.id(), now);
+                    }
+                }
+                self.events.push_back(Event { at, kind, entry });
+            }
+            while self.events.len() > CAPACITY {
+                self.events.pop_front();
+            }
+        }
+        self.previous = Some(scan.to_vec());
+
+        self.fresh
+            .retain(|_, since| since.elapsed() < HIGHLIGHT_FOR);
+        self.ghosts
+            .retain(|(_, since, _)| since.elapsed() < HIGHLIGHT_FOR);
+        // A listener that came straight back — its address has more rows
+        // than the siblings it left behind — is live again, not a ghost.
+        let live = by_key(scan);
+        self.ghosts
+            .retain(|(p, _, siblings)| live.get(&socket_key(p)).map_or(0, Vec::len) <= *siblings);
+    }
+
+    /// Newest first.
+    pub(crate) fn events(&self) -> impl Iterator<Item = &Event> {
+        self.events.iter().rev()
+    }
+
+    pub(crate) fn is_fresh(&self, p: &LsofEntry) -> bool {
+        self.fresh
+            .get(&p.id())
+            .is_some_and(|since| since.elapsed() < HIGHLIGHT_FOR)
+    }
+
+    /// Recently removed listeners, oldest removal first.
+    pub(crate) fn ghosts(&self) -> impl Iterator<Item = &LsofEntry> {
+        self.ghosts
+            .iter()
+            .filter(|(_, since, _)| since.elapsed() < HIGHLIGHT_FOR)
+            .map(|(p, _, _)| p)
+    }
+}
+
+/// Wall-clock `HH:MM:SS` in the local timezone, for timeline timestamps.
+pub(crate) fn format_clock(at: SystemTime) -> String {
+    let secs = at
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs() as libc::time_t)
+        .unwrap_or(0);
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&secs, &mut tm) }.is_null() {
+        return "--:--:--".to_string();
+    }
+    format!("{:02}:{:02}:{:02}", tm.tm_hour, tm.tm_min, tm.tm_sec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(pid: &str, port: u16, inode: u64) -> LsofEntry {
+        LsofEntry {
+            command: format!("server-{}", port),
+            ..LsofEntry::test(pid, port, inode)
+        }
+    }
+
+    #[test]
+    fn diff_reports_opened_closed_and_owner_changes() {
+        let old = vec![entry("10", 3000, 1), entry("20", 8080, 2)];
+        let new = vec![entry("30", 8080, 3), entry("40", 5173, 4)];
+        let events = diff(&old, &new);
+        assert_eq!(events.len(), 3);
+        assert!(matches!(events[0].0, EventKind::Closed));
+        assert_eq!(events[0].1.port, 3000);
+        match &events[1].0 {
+            EventKind::OwnerChanged { previous } => assert_eq!(previous.pid, "20"),
+            other => panic!("expected owner change, got {:?}", other),
+        }
+        assert_eq!(events[1].1.pid, "30");
+        assert!(matches!(events[2].0, EventKind::Opened));
+        assert_eq!(events[2].1.port, 5173);
+    }
+
+    #[test]
+    fn diff_ignores_unchanged_listeners() {
+        let scan = vec![entry("10", 3000, 1)];
+        assert!(diff(&scan, &scan).is_empty());
+    }
+
+    #[test]
+    fn reuseport_siblings_are_not_owner_changes() {
+        let workers = vec![entry("10", 8080, 1), entry("11", 8080, 2)];
+        assert!(diff(&workers, &workers).is_empty());
+        assert!(diff(&workers, &[workers[1].clone(), workers[0].clone()]).is_empty());
+
+        // One worker dies: it closed, its sibling carries on.
+        let events = diff(&workers, &workers[1..]);
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0].0, EventKind::Closed));
+        assert_eq!(events[0].1.pid, "10");
+
+        let mut timeline = Timeline::default();
+        timeline.observe(&workers);
+        timeline.observe(&workers[1..]);
+        let ghosts: Vec<&str> = timeline.ghosts().map(|p| p.pid.as_str()).collect();
+        assert_eq!(ghosts, ["10"]);
+    }
+
+    #[test]
+    fn first_scan_is_a_baseline_not_events() {
+        let mut timeline = Timeline::default();
+        timeline.observe(&[entry("10", 3000, 1)]);
+        assert_eq!(timeline.events().count(), 0);
+        assert!(!timeline.is_fresh(&entry("10", 3000, 1)));
+
+        timeline.observe(&[entry("10", 3000, 1), entry("20", 8080, 2)]);
+        assert_eq!(timeline.events().count(), 1);
+        assert!(timeline.is_fresh(&entry("20", 8080, 2)));
+
+        timeline.observe(&[entry("20", 8080, 2)]);
+        let ghosts: Vec<u16> = timeline.ghosts().map(|p| p.port).collect();
+        assert_eq!(ghosts, vec![3000]);
+        assert!(matches!(
+            timeline.events().next().unwrap().kind,
+            EventKind::Closed
+        ));
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut timeline = Timeline::default();
+        timeline.observe(&[]);
+        for i in 0..CAPACITY {
+            timeline.observe(&[entry("10", 1000 + i as u16, i as u64)]);
+        }
+        assert_eq!(timeline.events().count(), CAPACITY);
+    }
+}
@@ -3,11 +3,14 @@ use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Style},
     text::{Line, Span},
-    widgets::{Cell, Paragraph, Row, Table},
+    widgets::{Cell, Paragraph, Row, Table, Wrap},
 };
 
 use crate::app::{App, AppMode, SortBy};
-use crate::lsof::LsofEntry;
+use crate::exposure::Exposure;
+use crate::lsof::{self, LsofEntry, MemoryMetric};
+use crate::stuck::{StuckDiagnostics, Verdict};
+use crate::timeline::{self, EventKind};
 
 pub(crate) struct Colors;
 impl Colors {
@@ -22,6 +25,12 @@ impl Colors {
     /// Background tint for the selected row — dark teal that complements
     /// ACCENT without flattening the per-cell foreground colors.
     pub(crate) const SELECTED_BG: Color = Color::Rgb(20, 60, 55);
+    /// Background tint for a listener that just appeared — a dim green
+    /// that fades out (is dropped) after a few refreshes.
+    pub(crate) const FRESH_BG: Color = Color::Rgb(24, 48, 30);
+    /// The ◆ marking protected processes — steel blue, deliberately
+    /// unlike any of the warning colors: it means "careful", not "wrong".
+    pub(crate) const PROTECTED: Color = Color::Rgb(120, 160, 210);
 
     // Per-attribute hues. Reused both for the active sort-column
     // highlight and for status/confirmation messages so the user builds
@@ -32,8 +41,11 @@ impl Colors {
     pub(crate) const USER_HUE: Color = Color::Rgb(46, 204, 113);
     pub(crate) const COMMAND_HUE: Color = Color::Rgb(155, 89, 182);
     pub(crate) const MEMORY_HUE: Color = Color::Rgb(231, 76, 60);
+    pub(crate) const CPU_HUE: Color = Color::Rgb(236, 112, 175);
     pub(crate) const STARTTIME_HUE: Color = Color::Rgb(230, 126, 34);
     pub(crate) const PROTOCOL_HUE: Color = Color::Rgb(0, 200, 220);
+    pub(crate) const CONNECTIONS_HUE: Color = Color::Rgb(127, 140, 255);
+    pub(crate) const BACKLOG_HUE: Color = Color::Rgb(189, 195, 84);
 }
 
 fn get_loading_animation(frame: usize) -> &'static str {
@@ -75,6 +87,71 @@ fn highlight_matching_text(text: &str, query: &str, style: Style) -> Vec<Span<'s
     spans
 }
 
+const SPARK_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// One bar per sample, scaled so `lo` is the lowest bar and `hi` the
+/// tallest.
+fn sparkline(samples: &[f64], lo: f64, hi: f64) -> String {
+    let range = hi - lo;
+    samples
+        .iter()
+        .map(|v| {
+            if range <= 0.0 {
+                return SPARK_BARS[0];
+            }
+            let level = ((v - lo) / range * (SPARK_BARS.len() - 1) as f64).round();
+            SPARK_BARS[(level.max(0.0) as usize).min(SPARK_BARS.len() - 1)]
+        })
+        .collect()
+}
+
+/// Memory is scaled to its own min..max so slow growth is visible — but a
+/// window that barely moves (under 2% of its peak) stays flat, or the
+/// scaling would blow allocator noise up into a fake leak.
+fn memory_sparkline(samples: &[f64]) -> String {
+    let lo = samples.iter().copied().fold(f64::INFINITY, f64::min);
+    let hi = samples.iter().copied().fold(0.0, f64::max);
+    if hi - lo < hi * 0.02 {
+        return sparkline(samples, 0.0, 0.0);
+    }
+    sparkline(samples, lo, hi)
+}
+
+/// CPU is scaled from zero, with a 5% floor on the top so an idle server's
+/// 0.1% blips don't render as full-height spikes.
+fn cpu_sparkline(samples: &[f64]) -> String {
+    let hi = samples.iter().copied().fold(5.0, f64::max);
+    sparkline(samples, 0.0, hi)
+}
+
+/// Rows the timeline pane gets when toggled on: a title line, the newest
+/// events, and a blank separator above the detail panel.
+const TIMELINE_HEIGHT: u16 = 8;
+
+/// Rows for the peer list in Connections mode, title line included.
+const CONNECTIONS_HEIGHT: u16 = 8;
+
+/// Rows for the survived-SIGKILL diagnostics panel.
+const STUCK_HEIGHT: u16 = 6;
+
+/// Below this many rows — an `--inline` viewport, or just a small
+/// terminal — the header and detail lines give way to the table.
+const COMPACT_HEIGHT: u16 = 20;
+
+/// Question, spacer, choices. The spacer goes first when the band is
+/// squeezed, so the choices stay on screen.
+fn prompt_layout(area: Rect) -> std::rc::Rc<[Rect]> {
+    let spacer = if area.height >= 3 { 1 } else { 0 };
+    Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Length(spacer),
+            Constraint::Length(1),
+        ])
+        .split(area)
+}
+
 fn sort_color(sort_by: &SortBy) -> Color {
     match sort_by {
         SortBy::Port => Colors::PORT_HUE,
@@ -82,11 +159,41 @@ fn sort_color(sort_by: &SortBy) -> Color {
         SortBy::User => Colors::USER_HUE,
         SortBy::Command => Colors::COMMAND_HUE,
         SortBy::Memory => Colors::MEMORY_HUE,
+        SortBy::Cpu => Colors::CPU_HUE,
         SortBy::StartTime => Colors::STARTTIME_HUE,
         SortBy::Protocol => Colors::PROTOCOL_HUE,
+        SortBy::Connections => Colors::CONNECTIONS_HUE,
+        SortBy::Backlog => Colors::BACKLOG_HUE,
     }
 }
 
+/// Badge color for listeners worth flagging: wildcard binds are a
+/// warning (reachable on every network the machine joins), explicit
+/// public binds a danger. Everything more contained gets no badge.
+fn exposure_color(exposure: Exposure) -> Option<Color> {
+    match exposure {
+        Exposure::Wildcard => Some(Colors::WARNING),
+        Exposure::Public => Some(Colors::DANGER),
+        Exposure::Loopback | Exposure::LinkLocal | Exposure::Private => None,
+    }
+}
+
+fn exposure_style(exposure: Exposure) -> Style {
+    Style::default().fg(exposure_color(exposure).unwrap_or(Colors::TEXT_SECONDARY))
+}
+
+/// "127.0.0.1 on lo (loopback)", "* on all interfaces (wildcard)" —
+/// where the socket is bound and what that means for reachability.
+fn bind_description(p: &LsofEntry) -> String {
+    let exposure = p.exposure();
+    let on = match (&p.interface, exposure) {
+        (_, Exposure::Wildcard) => " on all interfaces".to_string(),
+        (Some(name), _) => format!(" on {}", name),
+        (None, _) => String::new(),
+    };
+    format!("{}{} ({})", p.local_addr, on, exposure.label())
+}
+
 /// Replace the user's $HOME prefix with `~` so `/home/aymen/testing/x`
 /// renders as `~/testing/x`. Only applies the shortening when the path
 /// crosses a directory boundary, to avoid mangling unrelated paths that
@@ -136,11 +243,76 @@ fn truncate(s: &str, max: usize) -> String {
     out
 }
 
+/// A process that outlived SIGKILL: its state, where in the kernel it's
+/// blocked, who traces or parents it — and what that means to do next.
+/// Re-read every refresh, so it shows the moment the process does go.
+fn render_stuck(d: &StuckDiagnostics, frame: &mut Frame, area: Rect) {
+    let dim = Style::default().fg(Colors::TEXT_TERTIARY);
+    let value = Style::default().fg(Colors::TEXT_SECONDARY);
+    let verdict = d.verdict();
+    let owner = |who: &Option<(u32, String)>| match who {
+        Some((pid, comm)) => format!("{} ({})", comm, pid),
+        None => "none".to_string(),
+    };
+
+    let mut state = vec![
+        Span::styled("state   ", dim),
+        Span::styled(
+            format!("{} ({})", d.state.unwrap_or('-'), d.state_label()),
+            Style::default().fg(Colors::WARNING).bold(),
+        ),
+    ];
+    if let Some(wchan) = &d.wchan {
+        state.push(Span::styled("  in ", dim));
+        state.push(Span::styled(wchan.clone(), value));
+    }
+    let stack = match &d.stack {
+        Some(frames) if !frames.is_empty() => frames.join(" ← "),
+        Some(_) => "empty".to_string(),
+        None => "unreadable — run with sudo to see it".to_string(),
+    };
+    let advice_color = if verdict == Verdict::Exited {
+        Colors::SUCCESS
+    } else {
+        Colors::ACCENT
+    };
+
+    let lines = vec![
+        Line::from(vec![
+            Span::styled(
+                format!("── {} ({}) survived SIGKILL", d.command, d.pid),
+                Style::default().fg(Colors::DANGER),
+            ),
+            Span::styled(" ─ Esc: dismiss", Style::default().fg(Colors::TEXT_MUTED)),
+        ]),
+        Line::from(state),
+        Line::from(vec![
+            Span::styled("stack   ", dim),
+            Span::styled(stack, value),
+        ]),
+        Line::from(vec![
+            Span::styled("traced  ", dim),
+            Span::styled(owner(&d.tracer), value),
+            Span::styled("   parent ", dim),
+            Span::styled(owner(&d.parent), value),
+        ]),
+        Line::from(vec![
+            Span::styled("→ ", Style::default().fg(advice_color).bold()),
+            Span::styled(verdict.advice(), Style::default().fg(advice_color)),
+        ]),
+    ];
+    frame.render_widget(Paragraph::new(lines), area);
+}
+
 impl App {
     pub(crate) fn render(&mut self, frame: &mut Frame) {
+        let compact = frame.area().height < COMPACT_HEIGHT;
         let chunks = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Length(5), Constraint::Min(0)])
+            .constraints([
+                Constraint::Length(if compact { 1 } else { 5 }), // just the title when compact
+                Constraint::Min(0),
+            ])
             .split(frame.area());
 
         self.render_header(frame, chunks[0]);
@@ -184,27 +356,58 @@ impl App {
             return;
         }
 
+        let timeline_height = if self.show_timeline {
+            TIMELINE_HEIGHT
+        } else {
+            0
+        };
+        let connections_height = if self.shows_connections() {
+            CONNECTIONS_HEIGHT
+        } else {
+            0
+        };
+        let stuck_height = if self.stuck.is_some() {
+            STUCK_HEIGHT
+        } else {
+            0
+        };
         let main_chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
-                Constraint::Min(0),    // process table
-                Constraint::Length(2), // detail line for the selected row
-                Constraint::Length(4), // status + help
+                // Compact: panels shrink before the table drops below a
+                // few rows.
+                Constraint::Min(if compact { 3 } else { 0 }), // process table
+                Constraint::Length(stuck_height),             // why a kill didn't take
+                Constraint::Length(connections_height),       // peers of the selected row (c)
+                Constraint::Length(timeline_height),          // event timeline (toggled)
+                Constraint::Length(if compact { 0 } else { 3 }), // detail lines for the selected row
+                Constraint::Length(if compact { 2 } else { 4 }), // status + help
             ])
             .split(chunks[1]);
 
+        // Ghosts go after every live row so the selection index (which
+        // only ever points into filtered_processes) stays valid.
         let rows: Vec<Row> = self
             .filtered_processes
             .iter()
             .map(|p| self.build_row(p))
+            .chain(
+                self.timeline
+                    .ghosts()
+                    .filter(|p| self.matches_filter(p))
+                    .map(|p| self.build_ghost_row(p)),
+            )
             .collect();
 
         let widths = [
-            Constraint::Length(7),  // PORT
+            Constraint::Length(10), // PORT (":65535 ● ◆": exposure dot, protected mark)
             Constraint::Length(14), // USER
-            Constraint::Length(8),  // MEM
+            Constraint::Length(9),  // MEM ("~1023.9MB": ~ marks RSS standing in)
+            Constraint::Length(7),  // CPU
             Constraint::Length(8),  // UPTIME
             Constraint::Length(7),  // PROTO (room for "PROTO ↑" header — was 5)
+            Constraint::Length(6),  // CONN
+            Constraint::Length(9),  // BACKLOG ("4096/4096")
             Constraint::Length(7),  // PID
             Constraint::Length(50), // COMMAND (last column, truncates if longer)
         ];
@@ -216,22 +419,140 @@ impl App {
         };
 
         let table = Table::new(rows, widths)
-            .header(self.build_header_row())
+            .header(self.build_header_row(compact))
             .row_highlight_style(Style::default().bg(Colors::SELECTED_BG).bold())
             .highlight_symbol(highlight_symbol)
             .column_spacing(2);
 
         frame.render_stateful_widget(table, main_chunks[0], &mut self.table_state);
 
-        self.render_selected_detail(frame, main_chunks[1]);
+        if let Some(stuck) = &self.stuck {
+            render_stuck(stuck, frame, main_chunks[1]);
+        }
 
-        self.render_status_and_help(frame, main_chunks[2]);
+        if self.shows_connections() {
+            self.render_connections(frame, main_chunks[2]);
+        }
+
+        if self.show_timeline {
+            self.render_timeline(frame, main_chunks[3]);
+        }
+
+        self.render_selected_detail(frame, main_chunks[4]);
+
+        self.render_status_and_help(frame, main_chunks[5]);
     }
 
-    /// Two-line panel directly below the table that shows the unabridged
-    /// COMMAND and CWD for the currently-selected row. The table cells
-    /// themselves truncate with `…` to stay scannable; this panel is where
-    /// you read the full text.
+    fn shows_connections(&self) -> bool {
+        matches!(self.mode, AppMode::Connections | AppMode::ConfirmDestroy)
+    }
+
+    /// Who is connected to the selected listener right now — the clients
+    /// a kill would drop. Scrolls to keep the peer cursor in view.
+    fn render_connections(&self, frame: &mut Frame, area: Rect) {
+        let dim = Style::default().fg(Colors::TEXT_TERTIARY);
+        let connections = self.selected_connections();
+        let mut lines = vec![Line::from(Span::styled(
+            format!(
+                "── {} connection{} ─ ↑/↓: select • x: close • c/Esc: back",
+                connections.len(),
+                if connections.len() == 1 { "" } else { "s" }
+            ),
+            Style::default().fg(Colors::TEXT_MUTED),
+        ))];
+        if connections.is_empty() {
+            lines.push(Line::from(Span::styled(
+                "no clients connected — killing this listener drops nobody",
+                dim,
+            )));
+        }
+        let shown = area.height.saturating_sub(1) as usize;
+        let skip = (self.connection_index + 1).saturating_sub(shown);
+        for (i, c) in connections.iter().enumerate().skip(skip).take(shown) {
+            let selected = i == self.connection_index;
+            let state_color = match c.state {
+                lsof::TcpState::Established => Colors::SUCCESS,
+                lsof::TcpState::SynRecv => Colors::TEXT_SECONDARY,
+                lsof::TcpState::CloseWait => Colors::WARNING,
+            };
+            let line = Line::from(vec![
+                Span::styled(
+                    if selected { "▶ " } else { "  " },
+                    Style::default().fg(Colors::ACCENT),
+                ),
+                Span::styled(
+                    format!("{:<12}", c.state.label()),
+                    Style::default().fg(state_color),
+                ),
+                Span::styled(
+                    c.remote_display(),
+                    Style::default().fg(Colors::CONNECTIONS_HUE),
+                ),
+            ]);
+            lines.push(if selected {
+                line.style(Style::default().bg(Colors::SELECTED_BG).bold())
+            } else {
+                line
+            });
+        }
+        frame.render_widget(Paragraph::new(lines), area);
+    }
+
+    /// Newest-first log of listeners opening, closing and changing hands
+    /// between scans, so short-lived ports aren't missed.
+    fn render_timeline(&self, frame: &mut Frame, area: Rect) {
+        let dim = Style::default().fg(Colors::TEXT_TERTIARY);
+        let mut lines = vec![Line::from(Span::styled(
+            "── timeline ─ t: hide",
+            Style::default().fg(Colors::TEXT_MUTED),
+        ))];
+        let shown = area.height.saturating_sub(2) as usize;
+        for event in self.timeline.events().take(shown) {
+            let p = &event.entry;
+            let (marker, color) = match event.kind {
+                EventKind::Opened => ("+ opened ", Colors::SUCCESS),
+                EventKind::Closed => ("- closed ", Colors::DANGER),
+                EventKind::OwnerChanged { .. } => ("~ owner  ", Colors::WARNING),
+            };
+            let mut spans = vec![
+                Span::styled(format!("{}  ", timeline::format_clock(event.at)), dim),
+                Span::styled(marker, Style::default().fg(color).bold()),
+                Span::styled(
+                    format!(":{:<6}", p.port),
+                    Style::default().fg(Colors::PORT_HUE).bold(),
+                ),
+            ];
+            if let EventKind::OwnerChanged { previous } = &event.kind {
+                spans.push(Span::styled(
+                    truncate(&previous.command, 30),
+                    Style::default().fg(Colors::TEXT_SECONDARY),
+                ));
+                spans.push(Span::styled(format!(" ({}) → ", previous.pid), dim));
+            }
+            spans.push(Span::styled(
+                truncate(&p.command, 40),
+                Style::default().fg(Colors::COMMAND_HUE),
+            ));
+            spans.push(Span::styled("  pid ", dim));
+            spans.push(Span::styled(
+                p.pid.clone(),
+                Style::default().fg(Colors::PID_HUE),
+            ));
+            lines.push(Line::from(spans));
+        }
+        if lines.len() == 1 {
+            lines.push(Line::from(Span::styled(
+                "no listeners opened or closed since reaper started",
+                dim,
+            )));
+        }
+        frame.render_widget(Paragraph::new(lines), area);
+    }
+
+    /// Panel directly below the table that shows the unabridged COMMAND
+    /// and CWD for the currently-selected row, plus its recent memory and
+    /// CPU trend. The table cells themselves truncate with `…` to stay
+    /// scannable; this panel is where you read the full text.
     fn render_selected_detail(&self, frame: &mut Frame, area: Rect) {
         let Some(p) = self.filtered_processes.get(self.selected_index) else {
             return;
@@ -242,7 +563,7 @@ impl App {
             .map(shorten_path)
             .unwrap_or_else(|| "—".to_string());
 
-        let lines = vec![
+        let mut lines = vec![
             Line::from(vec![
                 Span::styled("▌ ", Style::default().fg(Colors::ACCENT).bold()),
                 Span::styled(p.command.clone(), Style::default().fg(Colors::TEXT_PRIMARY)),
@@ -250,13 +571,98 @@ impl App {
             Line::from(vec![
                 Span::styled("↳ ", Style::default().fg(Colors::TEXT_TERTIARY)),
                 Span::styled(cwd_display, Style::default().fg(Colors::TEXT_SECONDARY)),
+                Span::styled("   bind ", Style::default().fg(Colors::TEXT_TERTIARY)),
+                Span::styled(bind_description(p), exposure_style(p.exposure())),
             ]),
         ];
+        if let Some((best, others)) = p.probable.split_first() {
+            let dim = Style::default().fg(Colors::TEXT_TERTIARY);
+            lines[0].spans.extend([
+                Span::styled("   probably: ", dim),
+                Span::styled(
+                    format!("{} (pid {})", best.command, best.pid),
+                    Style::default().fg(Colors::TEXT_SECONDARY),
+                ),
+                Span::styled(format!(" — {}", best.hint), dim),
+            ]);
+            if !others.is_empty() {
+                lines[0]
+                    .spans
+                    .push(Span::styled(format!(", +{} more", others.len()), dim));
+            }
+            lines[0].spans.push(Span::styled(
+                "  · unverified, can't be killed from here",
+                Style::default().fg(Colors::TEXT_MUTED),
+            ));
+        }
+        if let Some(protection) = self.protection(p) {
+            lines[0].spans.push(Span::styled(
+                format!("   ◆ {}", protection.reason()),
+                Style::default().fg(Colors::PROTECTED),
+            ));
+        }
+        if p.backlog.is_near_full() {
+            lines[1].spans.push(Span::styled(
+                format!(
+                    "   accept queue {} — not keeping up with accept()",
+                    p.backlog.display()
+                ),
+                Style::default().fg(Colors::DANGER).bold(),
+            ));
+        }
+
+        if let Some(trend) = self.history.get(p) {
+            let dim = Style::default().fg(Colors::TEXT_TERTIARY);
+            let memory: Vec<f64> = trend.memory_mb.iter().copied().collect();
+            let cpu: Vec<f64> = trend.cpu_percent.iter().copied().collect();
+            let mut spans = vec![
+                Span::styled("~ mem ", dim),
+                Span::styled(
+                    memory_sparkline(&memory),
+                    Style::default().fg(Colors::MEMORY_HUE),
+                ),
+                Span::styled(
+                    format!(" {}", p.get_memory_display(self.memory_metric)),
+                    dim,
+                ),
+            ];
+            if !cpu.is_empty() {
+                spans.push(Span::styled("   cpu ", dim));
+                spans.push(Span::styled(
+                    cpu_sparkline(&cpu),
+                    Style::default().fg(Colors::CPU_HUE),
+                ));
+                spans.push(Span::styled(format!(" {}", p.get_cpu_display()), dim));
+            }
+            // The full breakdown, whichever metric the column shows — and
+            // an honest note when PSS/USS/swap weren't readable.
+            let breakdown = match p.smaps {
+                Some(s) => format!(
+                    "   rss {} · pss {} · uss {} · swap {}",
+                    lsof::format_mb(p.memory_mb),
+                    lsof::format_mb(s.pss_mb),
+                    lsof::format_mb(s.uss_mb),
+                    lsof::format_mb(s.swap_mb)
+                ),
+                None if p.memory_is_rss(self.memory_metric) => format!(
+                    "   rss {} (smaps_rollup unreadable — ~ marks RSS in its place)",
+                    lsof::format_mb(p.memory_mb)
+                ),
+                None if self.memory_metric == MemoryMetric::Swap => format!(
+                    "   rss {} · swap {} (from /proc status)",
+                    lsof::format_mb(p.memory_mb),
+                    lsof::format_mb(p.swap_mb)
+                ),
+                None => String::new(),
+            };
+            spans.push(Span::styled(breakdown, dim));
+            lines.push(Line::from(spans));
+        }
 
         frame.render_widget(Paragraph::new(lines), area);
     }
 
-    fn build_header_row(&self) -> Row<'static> {
+    fn build_header_row(&self, compact: bool) -> Row<'static> {
         let base = Style::default().fg(Colors::TEXT_TERTIARY).bold();
         let active = Style::default().fg(sort_color(&self.sort_by)).bold();
         let arrow = if self.sort_ascending { "↑" } else { "↓" };
@@ -272,13 +678,16 @@ impl App {
         Row::new(vec![
             header_cell("PORT", SortBy::Port),
             header_cell("USER", SortBy::User),
-            header_cell("MEM", SortBy::Memory),
+            header_cell(self.memory_metric.label(), SortBy::Memory),
+            header_cell("CPU", SortBy::Cpu),
             header_cell("UPTIME", SortBy::StartTime),
             header_cell("PROTO", SortBy::Protocol),
+            header_cell("CONN", SortBy::Connections),
+            header_cell("BACKLOG", SortBy::Backlog),
             header_cell("PID", SortBy::Pid),
             header_cell("COMMAND", SortBy::Command),
         ])
-        .bottom_margin(1)
+        .bottom_margin(if compact { 0 } else { 1 })
     }
 
     fn build_row(&self, p: &LsofEntry) -> Row<'static> {
@@ -289,7 +698,7 @@ impl App {
 
         // Per-cell styling: search match wins, then active sort column, then the
         // base/dim style depending on whether the row is actionable.
-        let cell = |val: String, this: SortBy| -> Cell<'static> {
+        let spans = |val: String, this: SortBy| -> Vec<Span<'static>> {
             let row_default = if killable { base } else { dim };
             let column_default = if self.sort_by == this {
                 sort_style
@@ -297,15 +706,25 @@ impl App {
                 row_default
             };
             if self.search_query.is_empty() {
-                Cell::from(Line::from(Span::styled(val, column_default)))
+                vec![Span::styled(val, column_default)]
             } else {
-                Cell::from(Line::from(highlight_matching_text(
-                    &val,
-                    &self.search_query,
-                    column_default,
-                )))
+                highlight_matching_text(&val, &self.search_query, column_default)
             }
         };
+        let cell = |val: String, this: SortBy| -> Cell<'static> {
+            Cell::from(Line::from(spans(val, this)))
+        };
+
+        // Listeners reachable from outside (wildcard or public binds) get a
+        // colored dot after the port, so an accidental 0.0.0.0 dev server
+        // stands out at a glance.
+        let mut port = spans(format!(":{}", p.port), SortBy::Port);
+        if let Some(color) = exposure_color(p.exposure()) {
+            port.push(Span::styled(" ●", Style::default().fg(color)));
+        }
+        if self.protection(p).is_some() {
+            port.push(Span::styled(" ◆", Style::default().fg(Colors::PROTECTED)));
+        }
 
         let uptime = if p.start_time.is_some() {
             p.get_relative_time()
@@ -313,31 +732,89 @@ impl App {
             "—".to_string()
         };
         let memory = if killable {
-            p.get_memory_display()
+            p.get_memory_display(self.memory_metric)
         } else {
             "—".to_string()
         };
+        // A near-full accept queue is the "port open but server hung"
+        // signature — it overrides every other style so it can't be missed.
+        let backlog = if p.backlog.is_near_full() {
+            Cell::from(Span::styled(
+                p.backlog.display(),
+                Style::default().fg(Colors::DANGER).bold(),
+            ))
+        } else {
+            cell(p.backlog.display(), SortBy::Backlog)
+        };
+        let connections = if p.connections.is_empty() {
+            "·".to_string()
+        } else {
+            p.connections.len().to_string()
+        };
         // Cells whose content can exceed their column width get an explicit
         // ellipsis so a clipped cell is visually distinguishable from one
         // that fit. Narrow numeric/identifier columns aren't truncated —
         // they always fit their constraint.
-        Row::new(vec![
-            cell(format!(":{}", p.port), SortBy::Port),
+        let row = Row::new(vec![
+            Cell::from(Line::from(port)),
             cell(truncate(&p.user, 14), SortBy::User),
             cell(memory, SortBy::Memory),
+            cell(p.get_cpu_display(), SortBy::Cpu),
             cell(uptime, SortBy::StartTime),
             cell(p.protocol.to_string(), SortBy::Protocol),
+            cell(connections, SortBy::Connections),
+            backlog,
             cell(p.pid.clone(), SortBy::Pid),
             cell(truncate(&p.command, 50), SortBy::Command),
+        ]);
+        if self.timeline.is_fresh(p) {
+            row.style(Style::default().bg(Colors::FRESH_BG))
+        } else {
+            row
+        }
+    }
+
+    /// A listener that closed moments ago: muted and struck through, with
+    /// nothing but the identifying columns — its live stats are gone.
+    fn build_ghost_row(&self, p: &LsofEntry) -> Row<'static> {
+        let ghost = Style::default()
+            .fg(Colors::TEXT_MUTED)
+            .add_modifier(ratatui::style::Modifier::CROSSED_OUT);
+        Row::new(vec![
+            Cell::from(format!(":{}", p.port)),
+            Cell::from(truncate(&p.user, 14)),
+            Cell::from("—"),
+            Cell::from("—"),
+            Cell::from("closed"),
+            Cell::from(p.protocol),
+            Cell::from("—"),
+            Cell::from("—"),
+            Cell::from(p.pid.clone()),
+            Cell::from(truncate(&p.command, 50)),
         ])
+        .style(ghost)
     }
 
     fn render_header(&self, frame: &mut Frame, area: Rect) {
         let title_text = "reaper";
         let desc_text = "a linux tui for listing & killing listening ports";
         let process_count = self.filtered_processes.len();
-        let total_count = self.processes.len();
+        let total_count = self.rows().len();
         let hidden = self.restricted_hidden_count();
+        let missing: Vec<_> = self
+            .restricted_missing_caps()
+            .iter()
+            .map(|c| c.name())
+            .collect();
+        let restricted_note = if missing.is_empty() {
+            format!("{} restricted hidden — press 'a'", hidden)
+        } else {
+            format!(
+                "{} restricted hidden — press 'a'; {} would resolve them",
+                hidden,
+                missing.join(" + ")
+            )
+        };
 
         let info_text = if process_count == 0 && total_count == 0 {
             "Scanning active ports...".to_string()
@@ -349,15 +826,15 @@ impl App {
                 if total_count == 1 { "" } else { "es" }
             );
             if hidden > 0 {
-                s.push_str(&format!("({} restricted hidden — press 'a') ", hidden));
+                s.push_str(&format!("({}) ", restricted_note));
             }
             s
         } else if hidden > 0 {
             format!(
-                "{} process{} ({} restricted hidden — press 'a')",
+                "{} process{} ({})",
                 process_count,
                 if process_count == 1 { "" } else { "es" },
-                hidden
+                restricted_note
             )
         } else {
             format!(
@@ -367,39 +844,31 @@ impl App {
             )
         };
 
-        let info_widget = if self.mode == AppMode::Search {
-            Paragraph::new(vec![ratatui::text::Line::from(vec![
-                ratatui::text::Span::styled(info_text, Style::default().fg(Colors::TEXT_TERTIARY)),
-                ratatui::text::Span::styled(
-                    " [searching: ",
-                    Style::default().fg(Colors::TEXT_TERTIARY),
-                ),
-                ratatui::text::Span::styled(
+        let dim = Style::default().fg(Colors::TEXT_TERTIARY);
+        let mut info = vec![Span::styled(info_text, dim)];
+        if self.mode == AppMode::Search {
+            info.extend([
+                Span::styled(" [searching: ", dim),
+                Span::styled(
                     if self.search_query.is_empty() {
-                        "_"
+                        "_".to_string()
                     } else {
-                        &self.search_query
+                        self.search_query.clone()
                     },
                     Style::default().fg(Colors::ACCENT).bold(),
                 ),
-                ratatui::text::Span::styled("]", Style::default().fg(Colors::TEXT_TERTIARY)),
-            ])])
+                Span::styled("]", dim),
+            ]);
         } else if !self.search_query.is_empty() {
-            Paragraph::new(vec![ratatui::text::Line::from(vec![
-                ratatui::text::Span::styled(info_text, Style::default().fg(Colors::TEXT_TERTIARY)),
-                ratatui::text::Span::styled(
-                    "(filtered by: \"",
-                    Style::default().fg(Colors::TEXT_TERTIARY),
-                ),
-                ratatui::text::Span::styled(
+            info.extend([
+                Span::styled("(filtered by: \"", dim),
+                Span::styled(
                     self.search_query.clone(),
                     Style::default().fg(Colors::ACCENT).bold(),
                 ),
-                ratatui::text::Span::styled("\")", Style::default().fg(Colors::TEXT_TERTIARY)),
-            ])])
-        } else {
-            Paragraph::new(info_text).style(Style::default().fg(Colors::TEXT_TERTIARY))
-        };
+                Span::styled("\")", dim),
+            ]);
+        }
 
         let header_layout = Layout::default()
             .direction(Direction::Vertical)
@@ -411,22 +880,81 @@ impl App {
             ])
             .split(area);
 
+        // One line to spare: the process count, and the search or filter
+        // if any, stand in for the tagline.
+        let compact = area.height < 4;
+        let mut title = Line::from(vec![
+            Span::styled(title_text, Style::default().fg(Colors::ACCENT).bold()),
+            Span::styled(" • ", dim),
+        ]);
+        if compact {
+            title.spans.extend(info.clone());
+        } else {
+            title.spans.push(Span::styled(
+                desc_text,
+                Style::default().fg(Colors::TEXT_SECONDARY).bold(),
+            ));
+        }
+        if self.picking.is_some() {
+            title.spans.push(Span::raw("  "));
+            title.spans.push(Span::styled(
+                " PICK ",
+                Style::default()
+                    .fg(Colors::TEXT_PRIMARY)
+                    .bg(Colors::ACCENT)
+                    .bold(),
+            ));
+        } else if self.read_only {
+            title.spans.push(Span::raw("  "));
+            title.spans.push(Span::styled(
+                " READ-ONLY ",
+                Style::default()
+                    .fg(Colors::TEXT_PRIMARY)
+                    .bg(Colors::PROTECTED)
+                    .bold(),
+            ));
+        }
+        if let Some(agent) = &self.scanner.agent {
+            title.spans.push(Span::raw("  "));
+            title.spans.push(Span::styled(
+                " AGENT ",
+                Style::default()
+                    .fg(Colors::TEXT_PRIMARY)
+                    .bg(Colors::ACCENT)
+                    .bold(),
+            ));
+            if let Some(e) = &agent.error {
+                title.spans.push(Span::styled(
+                    format!(" unreachable at {}: {}", agent.path().display(), e),
+                    Style::default().fg(Colors::DANGER),
+                ));
+            }
+        }
+        if self.scanner.helper.is_some() {
+            title.spans.push(Span::raw("  "));
+            title.spans.push(Span::styled(
+                " ELEVATED ",
+                Style::default()
+                    .fg(Colors::TEXT_PRIMARY)
+                    .bg(Colors::DANGER)
+                    .bold(),
+            ));
+        }
+        if compact {
+            frame.render_widget(Paragraph::new(title), area);
+            return;
+        }
         frame.render_widget(
-            Paragraph::new(vec![Line::from(vec![
-                Span::styled(title_text, Style::default().fg(Colors::ACCENT).bold()),
-                Span::styled(" • ", Style::default().fg(Colors::TEXT_TERTIARY)),
-                Span::styled(
-                    desc_text,
-                    Style::default().fg(Colors::TEXT_SECONDARY).bold(),
-                ),
-            ])])
-            .alignment(Alignment::Left),
+            Paragraph::new(vec![title]).alignment(Alignment::Left),
             header_layout[0],
         );
 
         frame.render_widget(Paragraph::new(""), header_layout[1]);
 
-        frame.render_widget(info_widget.alignment(Alignment::Left), header_layout[2]);
+        frame.render_widget(
+            Paragraph::new(Line::from(info)).alignment(Alignment::Left),
+            header_layout[2],
+        );
     }
 
     fn render_status_and_help(&self, frame: &mut Frame, area: Rect) {
@@ -436,14 +964,16 @@ impl App {
             self.render_kill_prompt(frame, area);
             return;
         }
+        if self.mode == AppMode::ConfirmDestroy {
+            self.render_destroy_prompt(frame, area);
+            return;
+        }
 
+        // The key list has outgrown one line on narrower terminals; it
+        // wraps into the rows below the status line instead of clipping.
         let help_layout = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Length(1),
-                Constraint::Length(1),
-                Constraint::Length(1),
-            ])
+            .constraints([Constraint::Length(1), Constraint::Min(0)])
             .split(area);
 
         if let Some(status) = &self.status_message {
@@ -452,9 +982,29 @@ impl App {
         }
 
         let help_text = match self.mode {
+            AppMode::ProcessList if self.picking.is_some() => {
+                if self.search_query.is_empty() {
+                    "↑/↓: Navigate • ⏎: Pick • /: Search • s/0-9: Sort • a: Restricted • d: Dual-stack • r: Refresh • q/Esc: Cancel"
+                } else {
+                    &format!(
+                        "Search: \"{}\" • Esc: Clear search • ↑/↓: Navigate • ⏎: Pick",
+                        self.search_query
+                    )
+                }
+            }
+            AppMode::ProcessList if self.read_only => {
+                if self.search_query.is_empty() {
+                    "↑/↓: Navigate • /: Search • s/0-9: Sort • c: Connections • e: Export launch • a: Restricted • d: Dual-stack • m: Memory • t: Timeline • r: Refresh • q/Esc: Quit • read-only: killing is disabled"
+                } else {
+                    &format!(
+                        "Search: \"{}\" • Esc: Clear search • ↑/↓: Navigate",
+                        self.search_query
+                    )
+                }
+            }
             AppMode::ProcessList => {
                 if self.search_query.is_empty() {
-                    "↑/↓: Navigate • ⏎: Kill • /: Search • s/1-7: Sort • a: Restricted • r: Refresh • q/Esc: Quit"
+                    "↑/↓: Navigate • ⏎: Kill • /: Search • s/0-9: Sort • c: Connections • e: Export launch • a: Restricted • d: Dual-stack • m: Memory • t: Timeline • r: Refresh • q/Esc: Quit"
                 } else {
                     &format!(
                         "Search: \"{}\" • Esc: Clear search • ↑/↓: Navigate • ⏎: Kill",
@@ -462,14 +1012,21 @@ impl App {
                     )
                 }
             }
-            AppMode::ConfirmKill => unreachable!("handled above"),
+            AppMode::ConfirmKill | AppMode::ConfirmDestroy => unreachable!("handled above"),
             AppMode::Search => "Type to search • ⏎: Apply • Esc: Cancel",
+            AppMode::Connections if self.read_only => {
+                "↑/↓: Select peer • c/Esc: Back to listeners • q: Quit"
+            }
+            AppMode::Connections => {
+                "↑/↓: Select peer • x: Close connection • c/Esc: Back to listeners • q: Quit"
+            }
         };
 
         frame.render_widget(
             Paragraph::new(help_text)
                 .style(Style::default().fg(Colors::TEXT_MUTED))
-                .alignment(Alignment::Center),
+                .alignment(Alignment::Center)
+                .wrap(Wrap { trim: true }),
             help_layout[1],
         );
     }
@@ -499,25 +1056,87 @@ impl App {
             Span::styled(" ?", Style::default().fg(Colors::TEXT_PRIMARY).bold()),
         ]);
 
+        let choices = match &self.pending_protection {
+            // Protected: no y/Enter shortcut, the port has to be typed.
+            Some((reason, typed)) => Line::from(vec![
+                Span::styled(
+                    format!("◆ {} — type ", reason),
+                    Style::default().fg(Colors::PROTECTED),
+                ),
+                Span::styled(
+                    p.port.to_string(),
+                    Style::default().fg(Colors::PORT_HUE).bold(),
+                ),
+                Span::styled(
+                    " and Enter to kill: ",
+                    Style::default().fg(Colors::PROTECTED),
+                ),
+                Span::styled(
+                    format!("{}▏", typed),
+                    Style::default().fg(Colors::TEXT_PRIMARY).bold(),
+                ),
+                Span::styled(
+                    "        [Esc]",
+                    Style::default().fg(Colors::TEXT_TERTIARY).bold(),
+                ),
+                Span::styled(" cancel", Style::default().fg(Colors::TEXT_SECONDARY)),
+            ]),
+            None => Line::from(vec![
+                Span::styled("[y/Enter]", Style::default().fg(Colors::DANGER).bold()),
+                Span::styled(" kill        ", Style::default().fg(Colors::TEXT_SECONDARY)),
+                Span::styled("[r]", Style::default().fg(Colors::WARNING).bold()),
+                Span::styled(
+                    " kill & restart        ",
+                    Style::default().fg(Colors::TEXT_SECONDARY),
+                ),
+                Span::styled("[n/Esc]", Style::default().fg(Colors::TEXT_TERTIARY).bold()),
+                Span::styled(" cancel", Style::default().fg(Colors::TEXT_SECONDARY)),
+            ]),
+        };
+
+        let layout = prompt_layout(area);
+
+        frame.render_widget(Paragraph::new(prompt), layout[0]);
+        frame.render_widget(Paragraph::new(""), layout[1]);
+        frame.render_widget(Paragraph::new(choices), layout[2]);
+    }
+
+    /// Same shape as the kill prompt, for closing one client connection:
+    /// the server keeps running, that peer gets a reset.
+    fn render_destroy_prompt(&self, frame: &mut Frame, area: Rect) {
+        let Some(c) = self.pending_destroy.as_ref() else {
+            return;
+        };
+        let dim = Style::default().fg(Colors::TEXT_TERTIARY);
+
+        let prompt = Line::from(vec![
+            Span::styled("Close ", Style::default().fg(Colors::DANGER).bold()),
+            Span::styled(
+                c.remote_display(),
+                Style::default().fg(Colors::CONNECTIONS_HUE).bold(),
+            ),
+            Span::styled(" → ", dim),
+            Span::styled(
+                format!(":{}", c.local_port),
+                Style::default().fg(Colors::PORT_HUE).bold(),
+            ),
+            Span::styled(format!("  {}", c.state.label()), dim),
+            Span::styled(" ?", Style::default().fg(Colors::TEXT_PRIMARY).bold()),
+        ]);
+
         let choices = Line::from(vec![
             Span::styled("[y/Enter]", Style::default().fg(Colors::DANGER).bold()),
-            Span::styled(" kill        ", Style::default().fg(Colors::TEXT_SECONDARY)),
+            Span::styled(
+                " close (server keeps running)        ",
+                Style::default().fg(Colors::TEXT_SECONDARY),
+            ),
             Span::styled("[n/Esc]", Style::default().fg(Colors::TEXT_TERTIARY).bold()),
             Span::styled(" cancel", Style::default().fg(Colors::TEXT_SECONDARY)),
         ]);
 
-        let layout = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Length(1),
-                Constraint::Length(1),
-                Constraint::Length(1),
-                Constraint::Length(1),
-            ])
-            .split(area);
+        let layout = prompt_layout(area);
 
         frame.render_widget(Paragraph::new(prompt), layout[0]);
-        frame.render_widget(Paragraph::new(""), layout[1]);
         frame.render_widget(Paragraph::new(choices), layout[2]);
     }
 }
@@ -556,6 +1175,24 @@ mod tests {
         assert_eq!(underlined, vec!["Nginx", "nginx"]);
     }
 
+    #[test]
+    fn sparkline_scales_between_bounds() {
+        assert_eq!(sparkline(&[0.0, 50.0, 100.0], 0.0, 100.0), "▁▅█");
+        assert_eq!(sparkline(&[], 0.0, 100.0), "");
+    }
+
+    #[test]
+    fn memory_sparkline_shows_growth_but_not_noise() {
+        assert_eq!(memory_sparkline(&[100.0, 150.0, 200.0]), "▁▅█");
+        assert_eq!(memory_sparkline(&[100.0, 100.5, 100.2]), "▁▁▁");
+    }
+
+    #[test]
+    fn cpu_sparkline_does_not_amplify_idle_blips() {
+        assert_eq!(cpu_sparkline(&[0.0, 0.1, 0.2]), "▁▁▁");
+        assert_eq!(cpu_sparkline(&[0.0, 100.0]), "▁█");
+    }
+
     #[test]
     fn truncate_marks_clipped_cells() {
         assert_eq!(truncate("short", 10), "short");
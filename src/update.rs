@@ -0,0 +1,447 @@
+use std::fs;
+use std::io::{self, IsTerminal, Read};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use sha2::{Digest, Sha256};
+
+use crate::cli::UsageError;
+
+/// How long a candidate binary gets to answer `--version`.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// `reaper update [--from TARBALL [--sha256 FILE | --insecure]]`
+pub(crate) fn run(args: &[String]) -> Result<(), UsageError> {
+    let mut from = None;
+    let mut sha256 = None;
+    let mut insecure = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .map(PathBuf::from)
+                .ok_or_else(|| UsageError(format!("{} needs a file", arg)))
+        };
+        match arg.as_str() {
+            "--from" => from = Some(value()?),
+            "--sha256" => sha256 = Some(value()?),
+            "--insecure" => insecure = true,
+            other => return Err(UsageError(format!("unknown update option: {}", other))),
+        }
+    }
+    let Some(tarball) = from else {
+        if sha256.is_some() || insecure {
+            return Err(UsageError(
+                "--sha256 and --insecure go with --from TARBALL".to_string(),
+            ));
+        }
+        return online();
+    };
+    let result = std::env::current_exe()
+        .map_err(|e| format!("can't tell where reaper is installed: {}", e))
+        .and_then(|exe| install_from(&tarball, sha256.as_deref(), insecure, &exe));
+    if let Err(e) = result {
+        eprintln!("\n  {} {}", paint("31", "error"), e);
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Re-run the official installer, targeting the directory this binary
+/// runs from so the update lands in place regardless of where reaper was
+/// installed. The binary itself stays network-free: curl fetches, and the
+/// installer keeps sole ownership of checksum verification and messaging.
+fn online() -> Result<(), UsageError> {
+    let exe = std::env::current_exe().ok();
+    let Some(bin_dir) = exe.as_deref().and_then(Path::parent) else {
+        eprintln!("reaper update: could not determine where reaper is installed");
+        std::process::exit(1);
+    };
+    let status = Command::new("sh")
+        .args([
+            "-c",
+            "curl -LsSf https://reaper.aymenkrifa.com/install.sh | sh",
+        ])
+        .env("REAPER_BIN_DIR", bin_dir)
+        .status();
+    match status {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => {
+            eprintln!(
+                "\nupdate failed — if reaper lives in a system directory, try: sudo reaper update"
+            );
+            std::process::exit(status.code().unwrap_or(1));
+        }
+        Err(e) => {
+            eprintln!("reaper update: couldn't run the installer: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Colour only on a real terminal, honouring NO_COLOR — as the installer
+/// does.
+fn paint(code: &str, text: &str) -> String {
+    if io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none() {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// One progress line in the installer's format.
+fn ok(label: &str, detail: &str) {
+    println!("  {} {:<10} {}", paint("32", "✓"), label, detail);
+}
+
+fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+/// The digest from a `sha256sum`-style line: the first word, if it looks
+/// like one.
+fn parse_checksum(text: &str) -> Option<String> {
+    let word = text.split_whitespace().next()?;
+    (word.len() == 64 && word.bytes().all(|b| b.is_ascii_hexdigit()))
+        .then(|| word.to_ascii_lowercase())
+}
+
+/// Release assets ship `reaper-<target>.sha256` next to
+/// `reaper-<target>.tar.gz`; accept `<tarball>.sha256` too.
+fn sibling_checksum(tarball: &Path) -> Option<PathBuf> {
+    let name = tarball.file_name()?.to_str()?;
+    let stem = name.strip_suffix(".tar.gz").unwrap_or(name);
+    [format!("{}.sha256", stem), format!("{}.sha256", name)]
+        .into_iter()
+        .map(|n| tarball.with_file_name(n))
+        .find(|p| p.is_file())
+}
+
+/// A version as it orders: by release numbers, then a release above its
+/// own pre-releases, then pre-release against pre-release.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Version {
+    release: Vec<u64>,
+    stable: bool,
+    pre: Vec<PreChunk>,
+}
+
+/// A run of digits or of anything else in a pre-release tag, so that
+/// rc10 comes after rc9.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum PreChunk {
+    Num(u64),
+    Text(String),
+}
+
+/// "0.3.2" → [0, 3, 2]; "1.0.0-rc1" ranks below "1.0.0".
+fn parse_version(v: &str) -> Option<Version> {
+    let (release, pre) = match v.split_once('-') {
+        Some((release, pre)) => (release, Some(pre)),
+        None => (v, None),
+    };
+    let mut chunks = Vec::new();
+    let mut rest = pre.unwrap_or("");
+    while let Some(c) = rest.chars().next() {
+        let digits = c.is_ascii_digit();
+        let end = rest
+            .find(|c: char| c.is_ascii_digit() != digits)
+            .unwrap_or(rest.len());
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(match chunk.parse() {
+            Ok(n) if digits => PreChunk::Num(n),
+            _ => PreChunk::Text(chunk.to_string()),
+        });
+        rest = tail;
+    }
+    Some(Version {
+        release: release
+            .split('.')
+            .map(|n| n.parse().ok())
+            .collect::<Option<_>>()?,
+        stable: pre.is_none(),
+        pre: chunks,
+    })
+}
+
+/// What a binary says `--version` is, or None. Run in its own session:
+/// one too old to know the flag starts the TUI instead, and without a
+/// controlling terminal that fails at once rather than taking the screen.
+fn probe_version(binary: &Path) -> Option<String> {
+    let mut cmd = Command::new(binary);
+    cmd.arg("--version")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    unsafe {
+        cmd.pre_exec(|| {
+            libc::setsid();
+            Ok(())
+        });
+    }
+    let mut child = cmd.spawn().ok()?;
+    let started = Instant::now();
+    while child.try_wait().ok()?.is_none() {
+        if started.elapsed() > PROBE_TIMEOUT {
+            let _ = child.kill();
+            let _ = child.wait();
+            return None;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    let mut out = String::new();
+    child.stdout.take()?.read_to_string(&mut out).ok()?;
+    let mut words = out.lines().next()?.split_whitespace();
+    (words.next()? == "reaper")
+        .then(|| words.next().map(str::to_string))
+        .flatten()
+}
+
+/// Removes the scratch directory however the install ends.
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Verify, unpack and swap in a release tarball for `exe`. The old binary
+/// is kept as `<exe>.old`; the swap is a rename within one directory, so
+/// anything running `exe` meanwhile sees one binary or the other, never
+/// half of each. Without a checksum it refuses, unless `insecure` says
+/// the caller vouches for the tarball.
+fn install_from(
+    tarball: &Path,
+    checksum: Option<&Path>,
+    insecure: bool,
+    exe: &Path,
+) -> Result<(), String> {
+    let name = tarball.display();
+    let dir = exe
+        .parent()
+        .ok_or_else(|| format!("can't tell which directory {} is in", exe.display()))?;
+    // Unpacked beside the installed binary, so the final rename never
+    // crosses filesystems.
+    let scratch = Scratch(dir.join(format!(".reaper-update-{}", std::process::id())));
+    fs::create_dir(&scratch.0).map_err(|e| {
+        format!(
+            "can't write to {}: {} — if reaper lives in a system directory, try: sudo reaper update --from {}",
+            dir.display(),
+            e,
+            name
+        )
+    })?;
+    // Hash and unpack one private copy: the tarball itself could be
+    // swapped between the check and tar reading it.
+    let copy = scratch.0.join("release.tar.gz");
+    fs::copy(tarball, &copy).map_err(|e| format!("{}: {}", name, e))?;
+    let actual = sha256_file(&copy).map_err(|e| format!("{}: {}", name, e))?;
+    match checksum
+        .map(Path::to_path_buf)
+        .or_else(|| sibling_checksum(tarball))
+    {
+        Some(file) => {
+            let text =
+                fs::read_to_string(&file).map_err(|e| format!("{}: {}", file.display(), e))?;
+            let expected = parse_checksum(&text)
+                .ok_or_else(|| format!("{} holds no sha256 checksum", file.display()))?;
+            if expected != actual {
+                return Err("checksum mismatch — refusing to install.".to_string());
+            }
+            ok("verified", "sha256 checksum");
+        }
+        None if insecure => println!(
+            "  {} no checksum file given or found next to {} — not verified (--insecure)",
+            paint("33", "!"),
+            name
+        ),
+        None => {
+            return Err(format!(
+                "no checksum file given or found next to {} — refusing to install an \
+                 unverified binary. Pass --sha256 FILE, or --insecure if you trust the tarball.",
+                name
+            ));
+        }
+    }
+
+    let status = Command::new("tar")
+        .arg("-xzf")
+        .arg(&copy)
+        .arg("-C")
+        .arg(&scratch.0)
+        .status()
+        .map_err(|e| format!("couldn't run tar: {}", e))?;
+    let new = scratch.0.join("reaper");
+    // Not `is_file()`: that follows a symlink, and the chmod and the exec
+    // below would then land on whatever the tarball pointed it at.
+    let regular = fs::symlink_metadata(&new).is_ok_and(|m| m.file_type().is_file());
+    if !status.success() || !regular {
+        return Err(format!("{} did not contain the reaper binary.", name));
+    }
+    fs::set_permissions(&new, fs::Permissions::from_mode(0o755)).map_err(|e| e.to_string())?;
+
+    let new_ver = probe_version(&new)
+        .ok_or_else(|| "the unpacked binary doesn't report a version.".to_string())?;
+    let old_ver = probe_version(exe);
+    let newer = match (
+        parse_version(&new_ver),
+        old_ver.as_deref().and_then(parse_version),
+    ) {
+        (Some(new), Some(old)) => new > old,
+        (Some(_), None) => true,
+        (None, _) => false,
+    };
+    if !newer {
+        return Err(format!(
+            "v{} is not newer than the installed v{} — nothing to do.",
+            new_ver,
+            old_ver.as_deref().unwrap_or("?")
+        ));
+    }
+
+    let mut rollback = exe.as_os_str().to_owned();
+    rollback.push(".old");
+    let rollback = PathBuf::from(rollback);
+    fs::copy(exe, &rollback).map_err(|e| {
+        format!(
+            "couldn't keep a rollback copy at {}: {}",
+            rollback.display(),
+            e
+        )
+    })?;
+    fs::rename(&new, exe).map_err(|e| format!("couldn't replace {}: {}", exe.display(), e))?;
+    match old_ver {
+        Some(old) => ok("updated", &format!("v{} → v{}", old, new_ver)),
+        None => ok("updated", &format!("{}  (v{})", exe.display(), new_ver)),
+    }
+    ok("rollback", &rollback.display().to_string());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_versions_numerically() {
+        assert!(parse_version("0.3.10") > parse_version("0.3.9"));
+        assert!(parse_version("1.0.0-rc1") > parse_version("0.9.9"));
+        assert!(parse_version("1.0.0-rc1") < parse_version("1.0.0"));
+        assert!(parse_version("1.0.0-rc10") > parse_version("1.0.0-rc9"));
+        assert_eq!(parse_version("0.3.x"), None);
+        assert_eq!(
+            parse_checksum(&format!("{}  reaper.tar.gz\n", "AB".repeat(32))),
+            Some("ab".repeat(32))
+        );
+        assert_eq!(parse_checksum("not-a-digest reaper.tar.gz"), None);
+    }
+
+    #[test]
+    fn installs_a_verified_newer_tarball_and_keeps_a_rollback() {
+        let dir = std::env::temp_dir().join(format!("reaper-update-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let script = |version: &str| format!("#!/bin/sh\necho reaper {}\n", version);
+        fs::create_dir_all(dir.join("release")).unwrap();
+        fs::write(dir.join("release/reaper"), script("99.0.0")).unwrap();
+        let tarball = dir.join("reaper-x86_64-unknown-linux-musl.tar.gz");
+        let tar = Command::new("tar")
+            .arg("-czf")
+            .arg(&tarball)
+            .arg("-C")
+            .arg(dir.join("release"))
+            .arg("reaper")
+            .status()
+            .unwrap();
+        assert!(tar.success());
+        let exe = dir.join("reaper");
+        fs::write(&exe, script("0.1.0")).unwrap();
+        fs::set_permissions(&exe, fs::Permissions::from_mode(0o755)).unwrap();
+
+        // No checksum, no install.
+        assert!(
+            install_from(&tarball, None, false, &exe)
+                .unwrap_err()
+                .contains("refusing")
+        );
+        // A wrong checksum stops it before anything is touched.
+        let sum = dir.join("reaper-x86_64-unknown-linux-musl.sha256");
+        fs::write(&sum, format!("{}  x\n", "0".repeat(64))).unwrap();
+        assert!(
+            install_from(&tarball, None, false, &exe)
+                .unwrap_err()
+                .contains("mismatch")
+        );
+        assert_eq!(probe_version(&exe).as_deref(), Some("0.1.0"));
+
+        fs::write(&sum, sha256_file(&tarball).unwrap()).unwrap();
+        install_from(&tarball, None, false, &exe).unwrap();
+        assert_eq!(probe_version(&exe).as_deref(), Some("99.0.0"));
+        assert_eq!(
+            probe_version(&dir.join("reaper.old")).as_deref(),
+            Some("0.1.0")
+        );
+        // Same version again: refused, not reinstalled.
+        assert!(
+            install_from(&tarball, Some(&sum), false, &exe)
+                .unwrap_err()
+                .contains("not newer")
+        );
+        let leftovers = fs::read_dir(&dir)
+            .unwrap()
+            .filter(|e| {
+                e.as_ref()
+                    .unwrap()
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with(".reaper-update")
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+
+        // A `reaper` that is a symlink is refused without touching its
+        // target, even with --insecure.
+        let target = dir.join("target");
+        fs::write(&target, "data").unwrap();
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o600)).unwrap();
+        fs::remove_file(dir.join("release/reaper")).unwrap();
+        std::os::unix::fs::symlink(&target, dir.join("release/reaper")).unwrap();
+        let linked = dir.join("linked.tar.gz");
+        let tar = Command::new("tar")
+            .arg("-czf")
+            .arg(&linked)
+            .arg("-C")
+            .arg(dir.join("release"))
+            .arg("reaper")
+            .status()
+            .unwrap();
+        assert!(tar.success());
+        assert!(
+            install_from(&linked, None, true, &exe)
+                .unwrap_err()
+                .contains("did not contain")
+        );
+        assert_eq!(
+            fs::metadata(&target).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}